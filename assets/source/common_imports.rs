@@ -7,6 +7,10 @@
 pub struct EntityId(pub f64);
 
 impl EntityId {
+    pub fn missing() -> Self {
+        Self(f64::from_bits(u64::MAX))
+    }
+
     pub fn is_missing(&self) -> bool {
         self.0.to_bits() == u64::MAX
     }
@@ -43,14 +47,113 @@ pub const ATTACK_SPEED_MOD_ID: i32 = 1;
 pub const ATTACK_RANGE_MOD_ID: i32 = 2;
 pub const DODGE_CHANCE_ID: i32 = 3;
 
+pub const LOG_INFO: i32 = 0;
+pub const LOG_WARN: i32 = 1;
+
 extern "C" {
     pub fn despawn_entity(me: EntityId);
     pub fn spawn_harvestable_by_id(id: i32, real: Bool) -> EntityId;
     pub fn attach_child(me: EntityId, child: EntityId);
     pub fn play_sound(sound_id: i32);
     pub fn get_random() -> f32;
+    pub fn get_stat_id(ptr: *const u8, len: usize) -> i32;
 
     pub fn get_script_value(me: EntityId, script_value_id: i32, default: f32) -> f32;
     pub fn set_script_value(me: EntityId, script_value_id: i32, new_value: f32);
+    pub fn get_memory_i64(me: EntityId, slot: i32, default: i64) -> i64;
+    pub fn set_memory_i64(me: EntityId, slot: i32, value: i64);
+    pub fn get_memory_f64(me: EntityId, slot: i32, default: f64) -> f64;
+    pub fn set_memory_f64(me: EntityId, slot: i32, value: f64);
     pub fn heal_troop(me: EntityId, amount: i32);
 }
+
+// libm has a `log` too, which the linker would pick over the host's unless the module is named.
+#[link(wasm_import_module = "env")]
+extern "C" {
+    pub fn log(level: i32, ptr: *const u8, len: usize);
+}
+
+// Log text is gathered on the stack and handed to the host in one call. Anything past the buffer
+// is cut off. This avoids core::fmt too, which wasm-gc can't process.
+pub struct LogBuffer {
+    bytes: [u8; 256],
+    len: usize,
+}
+
+impl LogBuffer {
+    pub fn new() -> Self {
+        Self {
+            bytes: [0; 256],
+            len: 0,
+        }
+    }
+
+    pub fn push(&mut self, byte: u8) {
+        if self.len < self.bytes.len() {
+            self.bytes[self.len] = byte;
+            self.len += 1;
+        }
+    }
+
+    pub fn send(&self, level: i32) {
+        unsafe { log(level, self.bytes.as_ptr(), self.len) };
+    }
+}
+
+pub trait LogArg {
+    fn write_log(&self, buffer: &mut LogBuffer);
+}
+
+impl LogArg for &str {
+    fn write_log(&self, buffer: &mut LogBuffer) {
+        for byte in self.bytes() {
+            buffer.push(byte);
+        }
+    }
+}
+
+impl LogArg for i32 {
+    fn write_log(&self, buffer: &mut LogBuffer) {
+        let mut value = *self as i64;
+        if value < 0 {
+            buffer.push(b'-');
+            value = -value;
+        }
+        let mut divisor = 1;
+        while divisor * 10 <= value {
+            divisor *= 10;
+        }
+        while divisor > 0 {
+            buffer.push(b'0' + (value / divisor % 10) as u8);
+            divisor /= 10;
+        }
+    }
+}
+
+// Written with two decimals.
+impl LogArg for f32 {
+    fn write_log(&self, buffer: &mut LogBuffer) {
+        let hundredths = (*self * 100.) as i32;
+        if hundredths < 0 {
+            buffer.push(b'-');
+        }
+        ((hundredths / 100).abs()).write_log(buffer);
+        buffer.push(b'.');
+        let fraction = (hundredths % 100).abs();
+        if fraction < 10 {
+            buffer.push(b'0');
+        }
+        fraction.write_log(buffer);
+    }
+}
+
+// Logs the pieces through the host, which prefixes the script and the entity it runs for, e.g.
+// `log!(LOG_INFO, "health ", get_health(me))`.
+#[macro_export]
+macro_rules! log {
+    ($level:expr, $($arg:expr),* $(,)?) => {{
+        let mut buffer = $crate::common_imports::LogBuffer::new();
+        $($crate::common_imports::LogArg::write_log(&$arg, &mut buffer);)*
+        buffer.send($level);
+    }};
+}
@@ -2,6 +2,7 @@
 pub use common_imports::*;
 
 pub const RECRUIT: i32 = 3;
+pub const BLUEPRINT: i32 = 8;
 
 extern "C" {
     pub fn get_harvestable_id(me: EntityId) -> i32;
@@ -9,6 +10,7 @@ extern "C" {
     pub fn get_harvestable_is_real(me: EntityId) -> Bool;
     pub fn get_harvestable_is_plant(me: EntityId) -> Bool;
     pub fn get_harvestable_troop_id(me: EntityId) -> i32;
+    pub fn get_harvestable_tower_id(me: EntityId) -> i32;
 
     pub fn get_harvest_spot_progress(me: EntityId) -> f32;
     pub fn get_harvest_spot_progress_perc(me: EntityId) -> f32;
@@ -23,4 +25,6 @@ extern "C" {
     pub fn get_free_child_harvest_spot(me: EntityId) -> EntityId;
     pub fn set_visibility(me: EntityId, new_visibility: Bool);
     pub fn stage_troop(me: EntityId, troop_id: i32);
+    pub fn can_build_tower(me: EntityId) -> Bool;
+    pub fn build_tower(me: EntityId, tower_id: i32);
 }
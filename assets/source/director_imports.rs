@@ -0,0 +1,24 @@
+#![allow(dead_code)]
+pub use common_imports::*;
+
+// Spawn points, as indexes into the host's SPAWN_POINTS.
+pub const NORTH: i32 = 0;
+pub const EAST: i32 = 1;
+pub const SOUTH: i32 = 2;
+pub const WEST: i32 = 3;
+
+pub const SOLDIER: i32 = 0;
+pub const ARCHER: i32 = 1;
+pub const WARRIOR: i32 = 2;
+pub const RANGER: i32 = 3;
+
+extern "C" {
+    pub fn get_wave_id() -> i32;
+    pub fn get_army_size() -> i32;
+    pub fn get_invader_count() -> i32;
+
+    // Buffs apply to the next spawned invader only.
+    pub fn set_spawn_buff(script_value_id: i32, value: f32);
+    pub fn spawn_invader(spawn_point: i32, troop_id: i32) -> Bool;
+    pub fn end_wave();
+}
@@ -0,0 +1,44 @@
+#![allow(unused_variables)]
+mod common_imports;
+mod director_imports;
+use director_imports::*;
+
+pub const RAID_INTERVAL: f32 = 10.;
+pub const MAX_RAIDS: i32 = 4;
+pub const AMBUSH_WAVE: i32 = 2;
+pub const AMBUSH_TIME: f32 = 15.;
+
+static mut WAVE: i32 = -1;
+static mut RAIDS: i32 = 0;
+static mut AMBUSHED: bool = false;
+
+#[no_mangle]
+pub unsafe extern "C" fn director_tick(time_in_wave: f32) {
+    if time_in_wave < 0. {
+        return;
+    }
+    let wave = get_wave_id();
+    if wave != WAVE {
+        WAVE = wave;
+        RAIDS = 0;
+        AMBUSHED = false;
+    }
+    // Raiders keep coming while the player's army outnumbers the invaders.
+    while RAIDS < MAX_RAIDS && (RAIDS + 1) as f32 * RAID_INTERVAL < time_in_wave {
+        RAIDS += 1;
+        if get_army_size() > get_invader_count() {
+            set_spawn_buff(SPEED_MOD_ID, 1. + 0.1 * wave as f32);
+            spawn_invader((get_random() * 4.) as i32 % 4, WARRIOR);
+        }
+    }
+    if wave == AMBUSH_WAVE && !AMBUSHED && time_in_wave > AMBUSH_TIME {
+        AMBUSHED = true;
+        for spawn_point in [EAST, WEST] {
+            set_spawn_buff(ATTACK_SPEED_MOD_ID, 0.7);
+            spawn_invader(spawn_point, RANGER);
+        }
+    }
+    if time_in_wave > RAID_INTERVAL * 2. && get_invader_count() == 0 {
+        end_wave();
+    }
+}
@@ -0,0 +1,21 @@
+#![allow(unused_variables)]
+mod common_imports;
+mod delivery_imports;
+use delivery_imports::*;
+
+#[no_mangle]
+pub unsafe extern "C" fn can_receive(me: EntityId, delivery: EntityId) -> Bool {
+    if get_harvestable_tower_id(delivery) != -1
+        && get_harvestable_is_real(delivery) == Bool::r#true()
+    {
+        can_build_tower(me)
+    } else {
+        Bool::r#false()
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn receive(me: EntityId, delivery: EntityId, from: EntityId) {
+    build_tower(me, get_harvestable_tower_id(delivery));
+    despawn_entity(delivery);
+}
@@ -3,25 +3,46 @@ mod common_imports;
 mod troop_imports;
 use troop_imports::*;
 
+// Focus fire on the weakest enemy in range, to finish troops off quickly.
+unsafe fn weakest_enemy_in_range(me: EntityId) -> EntityId {
+    let mut weakest = EntityId::missing();
+    let mut weakest_health = i32::MAX;
+    for index in 0..get_enemy_count(me) {
+        let enemy = get_enemy(me, index);
+        let health = get_health(enemy);
+        if get_distance(me, enemy) <= get_attack_range(me) && health < weakest_health {
+            weakest = enemy;
+            weakest_health = health;
+        }
+    }
+    weakest
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn battle_action(me: EntityId) -> f32 {
     let nearest_enemy = get_nearest_enemy(me);
     scan_enemies(me);
     if nearest_enemy.is_missing() {
-        retreat(me, 32. * get_script_value(me, SPEED_MOD_ID, 1.0));
+        retreat(me, get_move_speed(me));
         0.0001
     } else {
-        if get_distance(me, nearest_enemy) > 128. {
+        if get_distance(me, nearest_enemy) > get_attack_range(me) {
             move_towards(
                 me,
                 get_x_of(nearest_enemy),
                 get_y_of(nearest_enemy),
-                32. * get_script_value(me, SPEED_MOD_ID, 1.0),
+                get_move_speed(me),
             );
             0.1
         } else {
             move_towards(me, 0., 0., 0.);
-            attack_enemy(me, nearest_enemy, 2) * get_script_value(me, ATTACK_SPEED_MOD_ID, 1.0)
+            let target = weakest_enemy_in_range(me);
+            let target = if target.is_missing() {
+                nearest_enemy
+            } else {
+                target
+            };
+            attack_enemy(me, target, 2) * get_script_value(me, ATTACK_SPEED_MOD_ID, 1.0)
         }
     }
 }
@@ -7,11 +7,38 @@ extern "C" {
     pub fn get_enemy(me: EntityId, index: i32) -> EntityId;
     pub fn get_nearest_enemy(me: EntityId) -> EntityId;
 
+    pub fn scan_allies(me: EntityId);
+    pub fn get_ally_count(me: EntityId) -> i32;
+    pub fn get_ally(me: EntityId, index: i32) -> EntityId;
+
+    pub fn get_health(troop: EntityId) -> i32;
+    pub fn get_max_health(troop: EntityId) -> i32;
+    pub fn get_troop_type_id(troop: EntityId) -> i32;
+    pub fn get_faction(entity: EntityId) -> i32;
+    pub fn get_cooldown_remaining(troop: EntityId) -> f32;
+    pub fn get_attackers_of(me: EntityId) -> i32;
+    pub fn get_attacker_of(me: EntityId, index: i32) -> EntityId;
+
     pub fn get_x_of(me: EntityId) -> f32;
     pub fn get_y_of(me: EntityId) -> f32;
     pub fn get_distance(me: EntityId, other: EntityId) -> f32;
+    pub fn get_move_speed(me: EntityId) -> f32;
+    pub fn get_attack_range(me: EntityId) -> f32;
 
     pub fn retreat(me: EntityId, speed: f32);
     pub fn move_towards(me: EntityId, x: f32, y: f32, speed: f32);
     pub fn attack_enemy(me: EntityId, enemy: EntityId, attack_id: i32) -> f32;
+
+    pub fn scan_structures(me: EntityId);
+    pub fn get_structure_count(me: EntityId) -> i32;
+    pub fn get_structure(me: EntityId, index: i32) -> EntityId;
+    pub fn get_nearest_structure(me: EntityId) -> EntityId;
+    pub fn get_structure_health(structure: EntityId) -> i32;
+    pub fn attack_structure(me: EntityId, structure: EntityId, attack_id: i32) -> f32;
+
+    pub fn define_attack(me: EntityId, base_id: i32, attack_id: i32) -> Bool;
+    pub fn get_attack_cooldown(attack_id: i32) -> f32;
+    pub fn set_attack_cooldown(me: EntityId, attack_id: i32, cooldown: f32) -> Bool;
+    pub fn get_attack_damage(attack_id: i32) -> i32;
+    pub fn set_attack_damage(me: EntityId, attack_id: i32, amount: i32) -> Bool;
 }
@@ -3,9 +3,15 @@ mod common_imports;
 mod troop_imports;
 use troop_imports::*;
 
+pub const RALLY: i32 = 0;
+pub const HEAL: i32 = 1;
+pub const WAR_CRY: i32 = 2;
+
+pub const ABILITY_RANGE: f32 = 128.;
+
 #[no_mangle]
 pub unsafe extern "C" fn battle_action(me: EntityId) -> f32 {
-    retreat(me, 64.);
+    retreat(me, get_move_speed(me));
     0.01
 }
 
@@ -13,3 +19,49 @@ pub unsafe extern "C" fn battle_action(me: EntityId) -> f32 {
 pub unsafe extern "C" fn on_death(me: EntityId) -> Bool {
     Bool::r#true()
 }
+
+#[no_mangle]
+pub unsafe extern "C" fn king_ability(me: EntityId, ability_id: i32) -> Bool {
+    match ability_id {
+        RALLY => {
+            // Nearby allies attack faster for the rest of the fight.
+            scan_allies(me);
+            let mut rallied = false;
+            for index in 0..get_ally_count(me) {
+                let ally = get_ally(me, index);
+                if get_distance(me, ally) < ABILITY_RANGE {
+                    let attack_speed = get_script_value(ally, ATTACK_SPEED_MOD_ID, 1.0);
+                    set_script_value(ally, ATTACK_SPEED_MOD_ID, attack_speed * 0.9);
+                    rallied = true;
+                }
+            }
+            rallied.into()
+        }
+        HEAL => {
+            heal_troop(me, 3);
+            scan_allies(me);
+            for index in 0..get_ally_count(me) {
+                let ally = get_ally(me, index);
+                if get_distance(me, ally) < ABILITY_RANGE {
+                    heal_troop(ally, 2);
+                }
+            }
+            Bool::r#true()
+        }
+        WAR_CRY => {
+            // Nearby enemies are slowed for the rest of the fight.
+            scan_enemies(me);
+            let mut frightened = false;
+            for index in 0..get_enemy_count(me) {
+                let enemy = get_enemy(me, index);
+                if get_distance(me, enemy) < ABILITY_RANGE {
+                    let speed = get_script_value(enemy, SPEED_MOD_ID, 1.0);
+                    set_script_value(enemy, SPEED_MOD_ID, speed * 0.75);
+                    frightened = true;
+                }
+            }
+            frightened.into()
+        }
+        _ => Bool::r#false(),
+    }
+}
@@ -8,15 +8,15 @@ pub unsafe extern "C" fn battle_action(me: EntityId) -> f32 {
     let nearest_enemy = get_nearest_enemy(me);
     scan_enemies(me);
     if nearest_enemy.is_missing() {
-        retreat(me, 32. * get_script_value(me, SPEED_MOD_ID, 1.0));
+        retreat(me, get_move_speed(me));
         0.0001
     } else {
-        if get_distance(me, nearest_enemy) > 256. {
+        if get_distance(me, nearest_enemy) > get_attack_range(me) {
             move_towards(
                 me,
                 get_x_of(nearest_enemy),
                 get_y_of(nearest_enemy),
-                32. * get_script_value(me, SPEED_MOD_ID, 1.0),
+                get_move_speed(me),
             );
             0.1
         } else {
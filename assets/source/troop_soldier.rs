@@ -7,16 +7,36 @@ use troop_imports::*;
 pub unsafe extern "C" fn battle_action(me: EntityId) -> f32 {
     let nearest_enemy = get_nearest_enemy(me);
     scan_enemies(me);
-    if nearest_enemy.is_missing() {
-        retreat(me, 32. * get_script_value(me, SPEED_MOD_ID, 1.0));
+    let nearest_structure = get_nearest_structure(me);
+    scan_structures(me);
+    if !nearest_structure.is_missing()
+        && (nearest_enemy.is_missing()
+            || get_distance(me, nearest_structure) < get_distance(me, nearest_enemy))
+    {
+        // Raid whatever farm building is closer than the defenders.
+        if get_distance(me, nearest_structure) > get_attack_range(me) {
+            move_towards(
+                me,
+                get_x_of(nearest_structure),
+                get_y_of(nearest_structure),
+                get_move_speed(me),
+            );
+            0.1
+        } else {
+            move_towards(me, 0., 0., 0.);
+            attack_structure(me, nearest_structure, 1)
+                * get_script_value(me, ATTACK_SPEED_MOD_ID, 1.0)
+        }
+    } else if nearest_enemy.is_missing() {
+        retreat(me, get_move_speed(me));
         0.0001
     } else {
-        if get_distance(me, nearest_enemy) > 36. {
+        if get_distance(me, nearest_enemy) > get_attack_range(me) {
             move_towards(
                 me,
                 get_x_of(nearest_enemy),
                 get_y_of(nearest_enemy),
-                32. * get_script_value(me, SPEED_MOD_ID, 1.0),
+                get_move_speed(me),
             );
             0.1
         } else {
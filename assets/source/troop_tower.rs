@@ -0,0 +1,21 @@
+#![allow(unused_variables)]
+mod common_imports;
+mod troop_imports;
+use troop_imports::*;
+
+// Towers never move, so they only shoot at whatever wanders into range.
+#[no_mangle]
+pub unsafe extern "C" fn battle_action(me: EntityId) -> f32 {
+    scan_enemies(me);
+    let nearest_enemy = get_nearest_enemy(me);
+    if nearest_enemy.is_missing() || get_distance(me, nearest_enemy) > get_attack_range(me) {
+        0.1
+    } else {
+        attack_enemy(me, nearest_enemy, 2) * get_script_value(me, ATTACK_SPEED_MOD_ID, 1.0)
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn on_death(me: EntityId) -> Bool {
+    Bool::r#true()
+}
@@ -7,23 +7,43 @@ use troop_imports::*;
 pub unsafe extern "C" fn battle_action(me: EntityId) -> f32 {
     let nearest_enemy = get_nearest_enemy(me);
     scan_enemies(me);
-    if nearest_enemy.is_missing() {
-        retreat(me, 32. * get_script_value(me, SPEED_MOD_ID, 1.0));
+    let nearest_structure = get_nearest_structure(me);
+    scan_structures(me);
+    if !nearest_structure.is_missing()
+        && (nearest_enemy.is_missing()
+            || get_distance(me, nearest_structure) < get_distance(me, nearest_enemy))
+    {
+        // Raid whatever farm building is closer than the defenders.
+        if get_distance(me, nearest_structure) > get_attack_range(me) * 0.75 {
+            move_towards(
+                me,
+                get_x_of(nearest_structure),
+                get_y_of(nearest_structure),
+                get_move_speed(me),
+            );
+            0.1
+        } else {
+            move_towards(me, 0., 0., 0.);
+            attack_structure(me, nearest_structure, 1)
+                * get_script_value(me, ATTACK_SPEED_MOD_ID, 1.0)
+        }
+    } else if nearest_enemy.is_missing() {
+        retreat(me, get_move_speed(me));
         0.0001
     } else {
-        if get_distance(me, nearest_enemy) > 36. {
+        if get_distance(me, nearest_enemy) > get_attack_range(me) * 0.75 {
             move_towards(
                 me,
                 get_x_of(nearest_enemy),
                 get_y_of(nearest_enemy),
-                32. * get_script_value(me, SPEED_MOD_ID, 1.0),
+                get_move_speed(me),
             );
             0.1
         } else {
             move_towards(me, 0., 0., 0.);
             for i in 0..get_enemy_count(me) {
                 let enemy = get_enemy(me, i);
-                if get_distance(me, enemy) <= 48. {
+                if get_distance(me, enemy) <= get_attack_range(me) {
                     attack_enemy(me, enemy, 1);
                 }
             }
@@ -36,3 +56,18 @@ pub unsafe extern "C" fn battle_action(me: EntityId) -> f32 {
 pub unsafe extern "C" fn on_death(me: EntityId) -> Bool {
     Bool::r#true()
 }
+
+// Warriors enrage once, the first time they drop below half health.
+const ENRAGED_ID: i32 = 100;
+
+#[no_mangle]
+pub unsafe extern "C" fn on_damaged(me: EntityId, attacker: EntityId, amount: i32) {
+    if get_script_value(me, ENRAGED_ID, 0.) == 0. && get_health(me) * 2 <= get_max_health(me) {
+        set_script_value(me, ENRAGED_ID, 1.);
+        set_script_value(
+            me,
+            ATTACK_SPEED_MOD_ID,
+            get_script_value(me, ATTACK_SPEED_MOD_ID, 1.0) * 0.5,
+        );
+    }
+}
@@ -0,0 +1,23 @@
+#![allow(unused_variables)]
+mod common_imports;
+mod delivery_imports;
+use delivery_imports::*;
+
+#[no_mangle]
+pub unsafe extern "C" fn can_receive(me: EntityId, delivery: EntityId) -> Bool {
+    if get_harvestable_id(delivery) == RECRUIT {
+        (!get_free_child_harvest_spot(me).is_missing()).into()
+    } else {
+        Bool::r#false()
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn receive(me: EntityId, delivery: EntityId, from: EntityId) {
+    let spot = get_free_child_harvest_spot(me);
+    if !spot.is_missing() {
+        set_visibility(spot, Bool::r#true());
+        set_harvest_spot_harvestable(spot, BLUEPRINT);
+    }
+    despawn_entity(delivery);
+}
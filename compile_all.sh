@@ -1,16 +1,20 @@
-./compile_rust.sh archery_field
-./compile_rust.sh child_spot
-./compile_rust.sh field_spot
-./compile_rust.sh market
-./compile_rust.sh practice_field
-./compile_rust.sh recruitment
-./compile_rust.sh staging
+./compile_rust.sh archery_field delivery
+./compile_rust.sh child_spot delivery
+./compile_rust.sh field_spot delivery
+./compile_rust.sh market delivery
+./compile_rust.sh practice_field delivery
+./compile_rust.sh recruitment delivery
+./compile_rust.sh staging delivery
 
-./compile_rust.sh deliver_enemy
-./compile_rust.sh deliver_king
-./compile_rust.sh deliver_troop_buffs
+./compile_rust.sh deliver_enemy delivery
+./compile_rust.sh deliver_king delivery
+./compile_rust.sh deliver_troop_buffs delivery
 ./compile_rust.sh troop_archer
 ./compile_rust.sh troop_warrior
 ./compile_rust.sh troop_soldier
 ./compile_rust.sh troop_ranger
-./compile_rust.sh troop_king
\ No newline at end of file
+./compile_rust.sh troop_king
+./compile_rust.sh troop_tower
+./compile_rust.sh tower_foundation delivery
+./compile_rust.sh workshop delivery
+./compile_rust.sh director_siege
\ No newline at end of file
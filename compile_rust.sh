@@ -1,3 +1,5 @@
-rustc --target wasm32-unknown-unknown --crate-type cdylib assets/source/$1.rs
+# Delivery scripts are loaded as `.delivery.wasm`: `sh compile_rust.sh market delivery`.
+# Scripts import their memory from the game, which is how it reads the text they log.
+rustc --target wasm32-unknown-unknown -C target-cpu=mvp -C opt-level=s -C link-arg=--import-memory --crate-type cdylib assets/source/$1.rs
 wasm-gc $1.wasm
-mv $1.wasm assets/scripts/
+mv $1.wasm assets/scripts/$1${2:+.$2}.wasm
@@ -0,0 +1,46 @@
+//! The soldier's troop script, written against the SDK.
+use harvests_sdk::prelude::*;
+
+enum Target {
+    Enemy(Entity),
+    Structure(Entity),
+}
+
+fn pick_target(me: Entity) -> Option<Target> {
+    match (me.nearest_enemy(), me.nearest_structure()) {
+        // Raid whatever farm building is closer than the defenders.
+        (Some(enemy), Some(structure)) if me.distance_to(structure) < me.distance_to(enemy) => {
+            Some(Target::Structure(structure))
+        }
+        (Some(enemy), _) => Some(Target::Enemy(enemy)),
+        (None, Some(structure)) => Some(Target::Structure(structure)),
+        (None, None) => None,
+    }
+}
+
+#[battle_action]
+fn battle_action(me: Entity) -> f32 {
+    let target = match pick_target(me) {
+        Some(target) => target,
+        None => {
+            me.retreat(me.move_speed());
+            return 0.0001;
+        }
+    };
+    let (Target::Enemy(entity) | Target::Structure(entity)) = target;
+    if !me.in_range_of(entity) {
+        me.move_towards_entity(entity);
+        return 0.1;
+    }
+    me.stop();
+    let cooldown = match target {
+        Target::Enemy(enemy) => me.attack(enemy, attacks::SLASH),
+        Target::Structure(structure) => me.attack_structure(structure, attacks::SLASH),
+    };
+    cooldown * me.script_value(script_values::ATTACK_SPEED_MOD, 1.)
+}
+
+#[on_death]
+fn on_death(_me: Entity) -> bool {
+    true
+}
@@ -0,0 +1,48 @@
+//! The delivery script for feeding fruit to allied troops, written against the SDK.
+use harvests_sdk::prelude::*;
+
+#[can_receive]
+fn can_receive(_me: Entity, delivery: Entity) -> bool {
+    delivery.is_real()
+        && matches!(
+            delivery.harvestable_id(),
+            Some(
+                harvestables::RED_BERRIES
+                    | harvestables::GRAPES
+                    | harvestables::LEMON
+                    | harvestables::PEAR
+            )
+        )
+}
+
+#[receive]
+fn receive(me: Entity, delivery: Entity, _from: Entity) {
+    match delivery.harvestable_id() {
+        Some(harvestables::RED_BERRIES) => me.heal((random() * 3.) as i32 + 4),
+        Some(harvestables::GRAPES) => {
+            let speed = me.script_value(script_values::SPEED_MOD, 1.);
+            me.set_script_value(script_values::SPEED_MOD, (speed * 1.2).clamp(1., 3.));
+        }
+        Some(harvestables::LEMON) => {
+            let attack_speed = me.script_value(script_values::ATTACK_SPEED_MOD, 1.);
+            me.set_script_value(
+                script_values::ATTACK_SPEED_MOD,
+                (attack_speed * 0.8).clamp(0.2, 1.),
+            );
+        }
+        Some(harvestables::PEAR) => {
+            let dodge = me.script_value(script_values::DODGE_CHANCE, 0.);
+            me.set_script_value(
+                script_values::DODGE_CHANCE,
+                1. - ((1. - dodge) * 0.8).clamp(0.25, 1.),
+            );
+        }
+        _ => {}
+    }
+    log!(
+        LogLevel::Info,
+        "fed ",
+        delivery.harvestable_id().unwrap_or(-1)
+    );
+    delivery.despawn();
+}
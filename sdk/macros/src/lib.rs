@@ -0,0 +1,147 @@
+//! Attributes that turn a plain function into the export the game calls.
+//!
+//! Each attribute keeps the function as written and adds an `extern "C"` wrapper under the
+//! export's name, converting arguments and the return value with `FromWasm`/`IntoWasm`.
+
+use proc_macro::{TokenStream, TokenTree};
+
+const ENTITY: &str = "::harvests_sdk::EntityId";
+const BOOL: &str = "::harvests_sdk::Bool";
+
+fn function_name(item: &TokenStream) -> Option<String> {
+    let mut tokens = item.clone().into_iter();
+    while let Some(token) = tokens.next() {
+        if let TokenTree::Ident(ident) = &token {
+            if ident.to_string() == "fn" {
+                if let Some(TokenTree::Ident(name)) = tokens.next() {
+                    return Some(name.to_string());
+                }
+            }
+        }
+    }
+    None
+}
+
+fn export(item: TokenStream, export: &str, params: &[&str], ret: Option<&str>) -> TokenStream {
+    let name = match function_name(&item) {
+        Some(name) => name,
+        None => {
+            return format!("compile_error!(\"#[{}] goes on a function\");", export)
+                .parse()
+                .unwrap()
+        }
+    };
+    let args: Vec<String> = params
+        .iter()
+        .enumerate()
+        .map(|(index, param)| format!("arg{}: {}", index, param))
+        .collect();
+    let call_args: Vec<String> = (0..params.len())
+        .map(|index| {
+            format!(
+                "::harvests_sdk::__export::FromWasm::from_wasm(arg{})",
+                index
+            )
+        })
+        .collect();
+    let call = format!("super::{}({})", name, call_args.join(", "));
+    let (ret, body) = match ret {
+        Some(ret) => (
+            format!(" -> {}", ret),
+            format!("::harvests_sdk::__export::IntoWasm::into_wasm({})", call),
+        ),
+        None => (String::new(), format!("{};", call)),
+    };
+    // The wrapper lives in its own module, so the function may share the export's name.
+    let wrapper = format!(
+        "#[doc(hidden)] mod __harvests_export_{export} {{
+            #[no_mangle]
+            pub extern \"C\" fn {export}({args}){ret} {{ {body} }}
+        }}",
+        export = export,
+        args = args.join(", "),
+        ret = ret,
+        body = body,
+    );
+    let mut output = item;
+    output.extend(wrapper.parse::<TokenStream>().unwrap());
+    output
+}
+
+/// `fn(me: Entity) -> f32`, returning the cooldown before the next action.
+#[proc_macro_attribute]
+pub fn battle_action(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    export(item, "battle_action", &[ENTITY], Some("f32"))
+}
+
+/// `fn(me: Entity) -> bool`, returning whether the troop really dies.
+#[proc_macro_attribute]
+pub fn on_death(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    export(item, "on_death", &[ENTITY], Some(BOOL))
+}
+
+/// `fn(me: Entity)`, called once the troop has been staged into battle.
+#[proc_macro_attribute]
+pub fn on_spawn(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    export(item, "on_spawn", &[ENTITY], None)
+}
+
+/// `fn(me: Entity, attacker: Entity, amount: i32)`
+#[proc_macro_attribute]
+pub fn on_damaged(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    export(item, "on_damaged", &[ENTITY, ENTITY, "i32"], None)
+}
+
+/// `fn(me: Entity, victim: Entity)`
+#[proc_macro_attribute]
+pub fn on_kill(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    export(item, "on_kill", &[ENTITY, ENTITY], None)
+}
+
+/// `fn(me: Entity, wave_id: i32)`
+#[proc_macro_attribute]
+pub fn on_wave_start(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    export(item, "on_wave_start", &[ENTITY, "i32"], None)
+}
+
+/// `fn(me: Entity, ability_id: i32) -> bool`, returning whether the ability went off.
+#[proc_macro_attribute]
+pub fn king_ability(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    export(item, "king_ability", &[ENTITY, "i32"], Some(BOOL))
+}
+
+/// `fn(time_in_wave: f32)`
+#[proc_macro_attribute]
+pub fn director_tick(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    export(item, "director_tick", &["f32"], None)
+}
+
+/// `fn(me: Entity) -> bool`
+#[proc_macro_attribute]
+pub fn can_produce(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    export(item, "can_produce", &[ENTITY], Some(BOOL))
+}
+
+/// `fn(me: Entity) -> Entity`, returning the produced harvestable or `Entity::missing()`.
+#[proc_macro_attribute]
+pub fn produce(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    export(item, "produce", &[ENTITY], Some(ENTITY))
+}
+
+/// `fn(me: Entity, delivery: Entity) -> bool`
+#[proc_macro_attribute]
+pub fn can_receive(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    export(item, "can_receive", &[ENTITY, ENTITY], Some(BOOL))
+}
+
+/// `fn(me: Entity, delivery: Entity, from: Entity)`
+#[proc_macro_attribute]
+pub fn receive(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    export(item, "receive", &[ENTITY, ENTITY, ENTITY], None)
+}
+
+/// `fn(me: Entity, delivery: Entity)`
+#[proc_macro_attribute]
+pub fn rejected(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    export(item, "rejected", &[ENTITY, ENTITY], None)
+}
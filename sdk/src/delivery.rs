@@ -0,0 +1,84 @@
+use crate::{entity::found_id, raw, Entity};
+
+// Delivery scripts, on harvestables and harvest spots.
+impl Entity {
+    pub fn harvestable_id(self) -> Option<i32> {
+        found_id(unsafe { raw::get_harvestable_id(self.0) })
+    }
+
+    /// Only real harvestables are worth anything.
+    pub fn harvestable_value(self) -> Option<i32> {
+        found_id(unsafe { raw::get_harvestable_value(self.0) })
+    }
+
+    pub fn is_real(self) -> bool {
+        unsafe { raw::get_harvestable_is_real(self.0) }.into()
+    }
+
+    pub fn is_plant(self) -> bool {
+        unsafe { raw::get_harvestable_is_plant(self.0) }.into()
+    }
+
+    /// The troop a recruit harvestable turns into.
+    pub fn harvestable_troop_id(self) -> Option<i32> {
+        found_id(unsafe { raw::get_harvestable_troop_id(self.0) })
+    }
+
+    /// The tower a blueprint harvestable builds.
+    pub fn harvestable_tower_id(self) -> Option<i32> {
+        found_id(unsafe { raw::get_harvestable_tower_id(self.0) })
+    }
+
+    pub fn harvest_progress(self) -> f32 {
+        unsafe { raw::get_harvest_spot_progress(self.0) }
+    }
+
+    pub fn set_harvest_progress(self, progress: f32) {
+        unsafe { raw::set_harvest_spot_progress(self.0, progress) }
+    }
+
+    pub fn harvest_progress_perc(self) -> f32 {
+        unsafe { raw::get_harvest_spot_progress_perc(self.0) }
+    }
+
+    pub fn set_harvest_progress_perc(self, progress_perc: f32) {
+        unsafe { raw::set_harvest_spot_progress_perc(self.0, progress_perc) }
+    }
+
+    pub fn harvest_time(self) -> f32 {
+        unsafe { raw::get_harvest_spot_harvest_time(self.0) }
+    }
+
+    pub fn set_harvest_time(self, harvest_time: f32) {
+        unsafe { raw::set_harvest_spot_harvest_time(self.0, harvest_time) }
+    }
+
+    pub fn planted(self) -> Option<Entity> {
+        Entity(unsafe { raw::get_harvest_spot_harvestable(self.0) }).found()
+    }
+
+    pub fn plant(self, harvestable_id: i32) {
+        unsafe { raw::set_harvest_spot_harvestable(self.0, harvestable_id) }
+    }
+
+    pub fn free_child_harvest_spot(self) -> Option<Entity> {
+        Entity(unsafe { raw::get_free_child_harvest_spot(self.0) }).found()
+    }
+
+    pub fn set_visible(self, visible: bool) {
+        unsafe { raw::set_visibility(self.0, visible.into()) }
+    }
+
+    /// Sends a troop out from this staging location.
+    pub fn stage_troop(self, troop_id: i32) {
+        unsafe { raw::stage_troop(self.0, troop_id) }
+    }
+
+    pub fn can_build_tower(self) -> bool {
+        unsafe { raw::can_build_tower(self.0) }.into()
+    }
+
+    pub fn build_tower(self, tower_id: i32) {
+        unsafe { raw::build_tower(self.0, tower_id) }
+    }
+}
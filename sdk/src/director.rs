@@ -0,0 +1,39 @@
+//! Calls for scenario director scripts.
+
+use crate::raw;
+
+/// Where invaders come from, matching the host's spawn point order.
+#[derive(Copy, Clone, PartialEq)]
+pub enum SpawnPoint {
+    North = 0,
+    East = 1,
+    South = 2,
+    West = 3,
+}
+
+pub fn wave_id() -> i32 {
+    unsafe { raw::get_wave_id() }
+}
+
+/// Troops on the player's side, the king and towers included.
+pub fn army_size() -> i32 {
+    unsafe { raw::get_army_size() }
+}
+
+pub fn invader_count() -> i32 {
+    unsafe { raw::get_invader_count() }
+}
+
+/// Buffs only apply to the next spawned invader.
+pub fn set_spawn_buff(script_value_id: i32, value: f32) {
+    unsafe { raw::set_spawn_buff(script_value_id, value) }
+}
+
+pub fn spawn_invader(spawn_point: SpawnPoint, troop_id: i32) -> bool {
+    unsafe { raw::spawn_invader(spawn_point as i32, troop_id) }.into()
+}
+
+/// Moves on to the next wave, or wins once there are none left.
+pub fn end_wave() {
+    unsafe { raw::end_wave() }
+}
@@ -0,0 +1,120 @@
+use crate::raw;
+
+// This is a transparent struct around u64, for use with entity ids.
+// f64 is the best type to cooperate with wasm-bindgen for web builds.
+// See: https://github.com/rustwasm/wasm-bindgen/issues/35
+#[derive(Copy, Clone, PartialEq)]
+#[repr(transparent)]
+pub struct EntityId(pub f64);
+
+impl EntityId {
+    pub fn missing() -> Self {
+        Self(f64::from_bits(u64::MAX))
+    }
+
+    pub fn is_missing(&self) -> bool {
+        self.0.to_bits() == u64::MAX
+    }
+}
+
+// This is a transparent struct around a bool-like value.
+// i8 cooperates with web builds.
+#[derive(Copy, Clone, PartialEq)]
+#[repr(transparent)]
+pub struct Bool(pub i8);
+
+impl From<bool> for Bool {
+    fn from(val: bool) -> Self {
+        Self(if val { 1 } else { 0 })
+    }
+}
+
+impl From<Bool> for bool {
+    fn from(val: Bool) -> Self {
+        val.0 == 1
+    }
+}
+
+// The host answers -1 for ids it couldn't find.
+pub(crate) fn found_id(id: i32) -> Option<i32> {
+    if id < 0 {
+        None
+    } else {
+        Some(id)
+    }
+}
+
+/// A handle to a game entity: a troop, structure, harvest spot or harvestable.
+///
+/// Which methods make sense depends on what the entity is and which script is running. Troop
+/// methods are only bound for troop scripts, delivery methods only for delivery scripts.
+#[derive(Copy, Clone, PartialEq)]
+#[repr(transparent)]
+pub struct Entity(pub EntityId);
+
+impl Entity {
+    pub fn missing() -> Self {
+        Self(EntityId::missing())
+    }
+
+    pub fn is_missing(&self) -> bool {
+        self.0.is_missing()
+    }
+
+    /// `None` for a missing entity, so lookups chain with `?`.
+    pub fn found(self) -> Option<Self> {
+        if self.is_missing() {
+            None
+        } else {
+            Some(self)
+        }
+    }
+
+    pub fn despawn(self) {
+        unsafe { raw::despawn_entity(self.0) }
+    }
+
+    pub fn attach_child(self, child: Entity) {
+        unsafe { raw::attach_child(self.0, child.0) }
+    }
+
+    /// See [`crate::ids::script_values`] for the ids the game reads.
+    pub fn script_value(self, script_value_id: i32, default: f32) -> f32 {
+        unsafe { raw::get_script_value(self.0, script_value_id, default) }
+    }
+
+    pub fn set_script_value(self, script_value_id: i32, value: f32) {
+        unsafe { raw::set_script_value(self.0, script_value_id, value) }
+    }
+
+    /// Memory slots keep their value between calls. There are 16, numbered from 0, and each holds
+    /// one i64, f64 or entity.
+    pub fn memory_i64(self, slot: i32, default: i64) -> i64 {
+        unsafe { raw::get_memory_i64(self.0, slot, default) }
+    }
+
+    pub fn set_memory_i64(self, slot: i32, value: i64) {
+        unsafe { raw::set_memory_i64(self.0, slot, value) }
+    }
+
+    pub fn memory_f64(self, slot: i32, default: f64) -> f64 {
+        unsafe { raw::get_memory_f64(self.0, slot, default) }
+    }
+
+    pub fn set_memory_f64(self, slot: i32, value: f64) {
+        unsafe { raw::set_memory_f64(self.0, slot, value) }
+    }
+
+    /// The entity kept in `slot`, missing if none was.
+    pub fn remembered(self, slot: i32) -> Entity {
+        Entity(EntityId(self.memory_f64(slot, EntityId::missing().0)))
+    }
+
+    pub fn remember(self, slot: i32, entity: Entity) {
+        self.set_memory_f64(slot, (entity.0).0)
+    }
+
+    pub fn heal(self, amount: i32) {
+        unsafe { raw::heal_troop(self.0, amount) }
+    }
+}
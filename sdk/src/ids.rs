@@ -0,0 +1,36 @@
+//! Ids shared with the game's data files.
+
+pub mod troops {
+    pub const SOLDIER: i32 = 0;
+    pub const ARCHER: i32 = 1;
+    pub const WARRIOR: i32 = 2;
+    pub const RANGER: i32 = 3;
+    pub const TOWER: i32 = 4;
+    pub const KING: i32 = 87;
+}
+
+pub mod harvestables {
+    pub const RED_BERRIES: i32 = 0;
+    pub const GRAPES: i32 = 1;
+    pub const LEMON: i32 = 2;
+    pub const RECRUIT: i32 = 3;
+    pub const SOLDIER: i32 = 4;
+    pub const ARCHER: i32 = 5;
+    pub const PEAR: i32 = 6;
+    pub const WATERMELON: i32 = 7;
+    pub const BLUEPRINT: i32 = 8;
+}
+
+pub mod attacks {
+    pub const SLASH: i32 = 1;
+    pub const ARROW: i32 = 2;
+    pub const RANGER_ARROW: i32 = 3;
+}
+
+/// The stats the game reads itself. Others can be looked up by key with [`crate::stat_id`].
+pub mod script_values {
+    pub const SPEED_MOD: i32 = 0;
+    pub const ATTACK_SPEED_MOD: i32 = 1;
+    pub const ATTACK_RANGE_MOD: i32 = 2;
+    pub const DODGE_CHANCE: i32 = 3;
+}
@@ -0,0 +1,114 @@
+//! Bindings for Harvests of War scripts.
+//!
+//! Scripts are `cdylib` crates built for `wasm32-unknown-unknown` with `-C target-cpu=mvp` and
+//! `--import-memory`. Start from the crate in `template`, whose `.cargo/config.toml` sets both.
+//! Mark plain functions with the export attributes and work through [`Entity`]:
+//!
+//! ```ignore
+//! use harvests_sdk::prelude::*;
+//!
+//! #[battle_action]
+//! fn act(me: Entity) -> f32 {
+//!     me.retreat(me.move_speed());
+//!     0.1
+//! }
+//! ```
+//!
+//! Calls that change the world only work on what the export was handed. Battle exports change
+//! their own troop and reach others by attacking, king abilities may change any troop, and
+//! delivery exports change the source or dropoff, its child spots and the delivered harvestable.
+//! Reaching past that traps, which quarantines the script.
+//!
+//! Scripts are checked as they load. A troop script needs `battle_action` and `on_death`, a
+//! delivery script (saved as `.delivery.wasm`) needs `can_produce` and `produce`, or
+//! `can_receive` and `receive`. Unknown imports and mismatched signatures fail the load.
+#![no_std]
+
+mod delivery;
+pub mod director;
+mod entity;
+pub mod ids;
+mod log;
+pub mod raw;
+mod troop;
+
+pub use entity::*;
+pub use harvests_sdk_macros::*;
+pub use log::*;
+pub use troop::*;
+
+pub mod prelude {
+    pub use crate::director::SpawnPoint;
+    pub use crate::ids::{attacks, harvestables, script_values, troops};
+    pub use crate::{define_attack, log, random, spawn_harvestable, stat_id, Entity, LogLevel};
+    pub use harvests_sdk_macros::*;
+}
+
+/// A random number in `0..1` from the game's seeded generator.
+pub fn random() -> f32 {
+    unsafe { raw::get_random() }
+}
+
+/// The id of the stat named `key` in the game's `assets/stats`, for stats a mod adds. Look it up
+/// once, in `on_spawn` or the first action, and keep it in memory.
+pub fn stat_id(key: &str) -> Option<i32> {
+    entity::found_id(unsafe { raw::get_stat_id(key.as_ptr(), key.len()) })
+}
+
+pub fn play_sound(sound_id: i32) {
+    unsafe { raw::play_sound(sound_id) }
+}
+
+/// Spawns a harvestable by id, hidden until something shows it. Fakes are the seed version.
+pub fn spawn_harvestable(harvestable_id: i32, real: bool) -> Entity {
+    Entity(unsafe { raw::spawn_harvestable_by_id(harvestable_id, real.into()) })
+}
+
+#[doc(hidden)]
+pub mod __export {
+    use crate::{Bool, Entity, EntityId};
+
+    pub trait FromWasm<T> {
+        fn from_wasm(value: T) -> Self;
+    }
+
+    pub trait IntoWasm<T> {
+        fn into_wasm(self) -> T;
+    }
+
+    impl FromWasm<EntityId> for Entity {
+        fn from_wasm(value: EntityId) -> Self {
+            Entity(value)
+        }
+    }
+
+    impl FromWasm<i32> for i32 {
+        fn from_wasm(value: i32) -> Self {
+            value
+        }
+    }
+
+    impl FromWasm<f32> for f32 {
+        fn from_wasm(value: f32) -> Self {
+            value
+        }
+    }
+
+    impl IntoWasm<EntityId> for Entity {
+        fn into_wasm(self) -> EntityId {
+            self.0
+        }
+    }
+
+    impl IntoWasm<Bool> for bool {
+        fn into_wasm(self) -> Bool {
+            self.into()
+        }
+    }
+
+    impl IntoWasm<f32> for f32 {
+        fn into_wasm(self) -> f32 {
+            self
+        }
+    }
+}
@@ -0,0 +1,100 @@
+use crate::raw;
+
+#[derive(Copy, Clone, PartialEq)]
+pub enum LogLevel {
+    Info = 0,
+    Warn = 1,
+}
+
+/// Log text, gathered on the stack and handed to the host in one call. Anything past 256 bytes is
+/// cut off. Building it without core::fmt keeps scripts small.
+pub struct LogBuffer {
+    bytes: [u8; 256],
+    len: usize,
+}
+
+impl Default for LogBuffer {
+    fn default() -> Self {
+        Self {
+            bytes: [0; 256],
+            len: 0,
+        }
+    }
+}
+
+impl LogBuffer {
+    pub fn push(&mut self, byte: u8) {
+        if self.len < self.bytes.len() {
+            self.bytes[self.len] = byte;
+            self.len += 1;
+        }
+    }
+
+    pub fn send(&self, level: LogLevel) {
+        unsafe { raw::log(level as i32, self.bytes.as_ptr(), self.len) }
+    }
+}
+
+pub trait LogArg {
+    fn write_log(&self, buffer: &mut LogBuffer);
+}
+
+impl LogArg for &str {
+    fn write_log(&self, buffer: &mut LogBuffer) {
+        for byte in self.bytes() {
+            buffer.push(byte);
+        }
+    }
+}
+
+impl LogArg for bool {
+    fn write_log(&self, buffer: &mut LogBuffer) {
+        if *self { "true" } else { "false" }.write_log(buffer);
+    }
+}
+
+impl LogArg for i32 {
+    fn write_log(&self, buffer: &mut LogBuffer) {
+        let mut value = *self as i64;
+        if value < 0 {
+            buffer.push(b'-');
+            value = -value;
+        }
+        let mut divisor = 1;
+        while divisor * 10 <= value {
+            divisor *= 10;
+        }
+        while divisor > 0 {
+            buffer.push(b'0' + (value / divisor % 10) as u8);
+            divisor /= 10;
+        }
+    }
+}
+
+// Written with two decimals.
+impl LogArg for f32 {
+    fn write_log(&self, buffer: &mut LogBuffer) {
+        let hundredths = (*self * 100.) as i32;
+        if hundredths < 0 {
+            buffer.push(b'-');
+        }
+        (hundredths / 100).abs().write_log(buffer);
+        buffer.push(b'.');
+        let fraction = (hundredths % 100).abs();
+        if fraction < 10 {
+            buffer.push(b'0');
+        }
+        fraction.write_log(buffer);
+    }
+}
+
+/// Logs the pieces through the host, which prefixes the script and the entity it runs for, e.g.
+/// `log!(LogLevel::Info, "health ", me.health())`.
+#[macro_export]
+macro_rules! log {
+    ($level:expr, $($arg:expr),* $(,)?) => {{
+        let mut buffer = $crate::LogBuffer::default();
+        $($crate::LogArg::write_log(&$arg, &mut buffer);)*
+        buffer.send($level);
+    }};
+}
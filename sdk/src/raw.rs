@@ -0,0 +1,106 @@
+//! The raw host imports, for anything the safe wrappers don't cover.
+
+use crate::{Bool, EntityId};
+
+// Bound for every script.
+extern "C" {
+    pub fn despawn_entity(me: EntityId);
+    pub fn spawn_harvestable_by_id(id: i32, real: Bool) -> EntityId;
+    pub fn attach_child(me: EntityId, child: EntityId);
+    pub fn play_sound(sound_id: i32);
+    pub fn get_random() -> f32;
+    pub fn get_stat_id(ptr: *const u8, len: usize) -> i32;
+
+    pub fn get_script_value(me: EntityId, script_value_id: i32, default: f32) -> f32;
+    pub fn set_script_value(me: EntityId, script_value_id: i32, new_value: f32);
+    pub fn get_memory_i64(me: EntityId, slot: i32, default: i64) -> i64;
+    pub fn set_memory_i64(me: EntityId, slot: i32, value: i64);
+    pub fn get_memory_f64(me: EntityId, slot: i32, default: f64) -> f64;
+    pub fn set_memory_f64(me: EntityId, slot: i32, value: f64);
+    pub fn heal_troop(me: EntityId, amount: i32);
+}
+
+// libm has a `log` too, which the linker would pick over the host's unless the module is named.
+#[link(wasm_import_module = "env")]
+extern "C" {
+    pub fn log(level: i32, ptr: *const u8, len: usize);
+}
+
+// Bound for troop scripts.
+extern "C" {
+    pub fn scan_enemies(me: EntityId);
+    pub fn get_enemy_count(me: EntityId) -> i32;
+    pub fn get_enemy(me: EntityId, index: i32) -> EntityId;
+    pub fn get_nearest_enemy(me: EntityId) -> EntityId;
+
+    pub fn scan_allies(me: EntityId);
+    pub fn get_ally_count(me: EntityId) -> i32;
+    pub fn get_ally(me: EntityId, index: i32) -> EntityId;
+
+    pub fn get_health(troop: EntityId) -> i32;
+    pub fn get_max_health(troop: EntityId) -> i32;
+    pub fn get_troop_type_id(troop: EntityId) -> i32;
+    pub fn get_faction(entity: EntityId) -> i32;
+    pub fn get_cooldown_remaining(troop: EntityId) -> f32;
+    pub fn get_attackers_of(me: EntityId) -> i32;
+    pub fn get_attacker_of(me: EntityId, index: i32) -> EntityId;
+
+    pub fn get_x_of(me: EntityId) -> f32;
+    pub fn get_y_of(me: EntityId) -> f32;
+    pub fn get_distance(me: EntityId, other: EntityId) -> f32;
+    pub fn get_move_speed(me: EntityId) -> f32;
+    pub fn get_attack_range(me: EntityId) -> f32;
+
+    pub fn retreat(me: EntityId, speed: f32);
+    pub fn move_towards(me: EntityId, x: f32, y: f32, speed: f32);
+    pub fn attack_enemy(me: EntityId, enemy: EntityId, attack_id: i32) -> f32;
+
+    pub fn scan_structures(me: EntityId);
+    pub fn get_structure_count(me: EntityId) -> i32;
+    pub fn get_structure(me: EntityId, index: i32) -> EntityId;
+    pub fn get_nearest_structure(me: EntityId) -> EntityId;
+    pub fn get_structure_health(structure: EntityId) -> i32;
+    pub fn attack_structure(me: EntityId, structure: EntityId, attack_id: i32) -> f32;
+
+    pub fn define_attack(me: EntityId, base_id: i32, attack_id: i32) -> Bool;
+    pub fn get_attack_cooldown(attack_id: i32) -> f32;
+    pub fn set_attack_cooldown(me: EntityId, attack_id: i32, cooldown: f32) -> Bool;
+    pub fn get_attack_damage(attack_id: i32) -> i32;
+    pub fn set_attack_damage(me: EntityId, attack_id: i32, amount: i32) -> Bool;
+}
+
+// Bound for delivery scripts.
+extern "C" {
+    pub fn get_harvestable_id(me: EntityId) -> i32;
+    pub fn get_harvestable_value(me: EntityId) -> i32;
+    pub fn get_harvestable_is_real(me: EntityId) -> Bool;
+    pub fn get_harvestable_is_plant(me: EntityId) -> Bool;
+    pub fn get_harvestable_troop_id(me: EntityId) -> i32;
+    pub fn get_harvestable_tower_id(me: EntityId) -> i32;
+
+    pub fn get_harvest_spot_progress(me: EntityId) -> f32;
+    pub fn get_harvest_spot_progress_perc(me: EntityId) -> f32;
+    pub fn get_harvest_spot_harvest_time(me: EntityId) -> f32;
+    pub fn get_harvest_spot_harvestable(me: EntityId) -> EntityId;
+
+    pub fn set_harvest_spot_progress(me: EntityId, progress: f32);
+    pub fn set_harvest_spot_progress_perc(me: EntityId, progress_perc: f32);
+    pub fn set_harvest_spot_harvest_time(me: EntityId, harvest_time: f32);
+    pub fn set_harvest_spot_harvestable(me: EntityId, harvestable_id: i32);
+
+    pub fn get_free_child_harvest_spot(me: EntityId) -> EntityId;
+    pub fn set_visibility(me: EntityId, new_visibility: Bool);
+    pub fn stage_troop(me: EntityId, troop_id: i32);
+    pub fn can_build_tower(me: EntityId) -> Bool;
+    pub fn build_tower(me: EntityId, tower_id: i32);
+}
+
+// Bound for director scripts.
+extern "C" {
+    pub fn get_wave_id() -> i32;
+    pub fn get_army_size() -> i32;
+    pub fn get_invader_count() -> i32;
+    pub fn set_spawn_buff(script_value_id: i32, value: f32);
+    pub fn spawn_invader(spawn_point: i32, troop_id: i32) -> Bool;
+    pub fn end_wave();
+}
@@ -0,0 +1,151 @@
+use crate::{entity::found_id, raw, Entity};
+
+/// Walks a list the host just scanned, by index.
+fn scanned(
+    me: Entity,
+    count: unsafe extern "C" fn(crate::EntityId) -> i32,
+    get: unsafe extern "C" fn(crate::EntityId, i32) -> crate::EntityId,
+) -> impl Iterator<Item = Entity> {
+    let count = unsafe { count(me.0) };
+    (0..count).map(move |index| Entity(unsafe { get(me.0, index) }))
+}
+
+// Troop scripts.
+impl Entity {
+    pub fn health(self) -> i32 {
+        unsafe { raw::get_health(self.0) }
+    }
+
+    pub fn max_health(self) -> i32 {
+        unsafe { raw::get_max_health(self.0) }
+    }
+
+    pub fn troop_type_id(self) -> Option<i32> {
+        found_id(unsafe { raw::get_troop_type_id(self.0) })
+    }
+
+    pub fn faction(self) -> Option<i32> {
+        found_id(unsafe { raw::get_faction(self.0) })
+    }
+
+    pub fn cooldown_remaining(self) -> f32 {
+        unsafe { raw::get_cooldown_remaining(self.0) }
+    }
+
+    pub fn x(self) -> f32 {
+        unsafe { raw::get_x_of(self.0) }
+    }
+
+    pub fn y(self) -> f32 {
+        unsafe { raw::get_y_of(self.0) }
+    }
+
+    pub fn distance_to(self, other: Entity) -> f32 {
+        unsafe { raw::get_distance(self.0, other.0) }
+    }
+
+    /// Base speed with buffs applied.
+    pub fn move_speed(self) -> f32 {
+        unsafe { raw::get_move_speed(self.0) }
+    }
+
+    /// Base range with buffs applied. Attacks on anything further away don't land.
+    pub fn attack_range(self) -> f32 {
+        unsafe { raw::get_attack_range(self.0) }
+    }
+
+    pub fn in_range_of(self, other: Entity) -> bool {
+        self.distance_to(other) <= self.attack_range()
+    }
+
+    pub fn nearest_enemy(self) -> Option<Entity> {
+        Entity(unsafe { raw::get_nearest_enemy(self.0) }).found()
+    }
+
+    pub fn nearest_structure(self) -> Option<Entity> {
+        Entity(unsafe { raw::get_nearest_structure(self.0) }).found()
+    }
+
+    pub fn enemies(self) -> impl Iterator<Item = Entity> {
+        unsafe { raw::scan_enemies(self.0) };
+        scanned(self, raw::get_enemy_count, raw::get_enemy)
+    }
+
+    pub fn allies(self) -> impl Iterator<Item = Entity> {
+        unsafe { raw::scan_allies(self.0) };
+        scanned(self, raw::get_ally_count, raw::get_ally)
+    }
+
+    pub fn structures(self) -> impl Iterator<Item = Entity> {
+        unsafe { raw::scan_structures(self.0) };
+        scanned(self, raw::get_structure_count, raw::get_structure)
+    }
+
+    /// Troops with an attack on the way to this one.
+    pub fn attackers(self) -> impl Iterator<Item = Entity> {
+        scanned(self, raw::get_attackers_of, raw::get_attacker_of)
+    }
+
+    pub fn structure_health(self) -> i32 {
+        unsafe { raw::get_structure_health(self.0) }
+    }
+
+    pub fn move_towards(self, x: f32, y: f32, speed: f32) {
+        unsafe { raw::move_towards(self.0, x, y, speed) }
+    }
+
+    pub fn move_towards_entity(self, target: Entity) {
+        self.move_towards(target.x(), target.y(), self.move_speed());
+    }
+
+    pub fn stop(self) {
+        self.move_towards(0., 0., 0.);
+    }
+
+    /// Heads back to where the troop was staged.
+    pub fn retreat(self, speed: f32) {
+        unsafe { raw::retreat(self.0, speed) }
+    }
+
+    /// Returns the cooldown of the attack, or 0 if it couldn't be made.
+    pub fn attack(self, enemy: Entity, attack_id: i32) -> f32 {
+        unsafe { raw::attack_enemy(self.0, enemy.0, attack_id) }
+    }
+
+    /// Returns the cooldown of the attack, or 0 if it couldn't be made.
+    pub fn attack_structure(self, structure: Entity, attack_id: i32) -> f32 {
+        unsafe { raw::attack_structure(self.0, structure.0, attack_id) }
+    }
+}
+
+/// Defines `attack_id` as a copy of `base_id` and its miss, to tune with the setters below. It
+/// belongs to `me`'s troop type, which alone may change it. False if `base_id` doesn't exist or
+/// `attack_id` is one of the game's own attacks or another troop type's.
+pub fn define_attack(me: Entity, base_id: i32, attack_id: i32) -> bool {
+    unsafe { raw::define_attack(me.0, base_id, attack_id) }.into()
+}
+
+pub fn attack_cooldown(attack_id: i32) -> Option<f32> {
+    let cooldown = unsafe { raw::get_attack_cooldown(attack_id) };
+    if cooldown < 0. {
+        None
+    } else {
+        Some(cooldown)
+    }
+}
+
+/// Only attacks `me`'s troop type defined can be changed.
+pub fn set_attack_cooldown(me: Entity, attack_id: i32, cooldown: f32) -> bool {
+    unsafe { raw::set_attack_cooldown(me.0, attack_id, cooldown) }.into()
+}
+
+/// The damage of every hit in the attack, together.
+pub fn attack_damage(attack_id: i32) -> Option<i32> {
+    found_id(unsafe { raw::get_attack_damage(attack_id) })
+}
+
+/// Splits `amount` evenly over the hits of the attack. Only attacks `me`'s troop type defined can
+/// be changed.
+pub fn set_attack_damage(me: Entity, attack_id: i32, amount: i32) -> bool {
+    unsafe { raw::set_attack_damage(me.0, attack_id, amount) }.into()
+}
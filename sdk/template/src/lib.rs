@@ -0,0 +1,24 @@
+//! A troop that attacks the nearest enemy and backs off when there is none.
+use harvests_sdk::prelude::*;
+
+#[battle_action]
+fn battle_action(me: Entity) -> f32 {
+    match me.nearest_enemy() {
+        Some(enemy) if me.distance_to(enemy) <= me.attack_range() => {
+            me.attack(enemy, attacks::SLASH)
+        }
+        Some(enemy) => {
+            me.move_towards_entity(enemy);
+            0.1
+        }
+        None => {
+            me.retreat(me.move_speed());
+            0.1
+        }
+    }
+}
+
+#[on_death]
+fn on_death(_me: Entity) -> bool {
+    true
+}
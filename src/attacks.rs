@@ -10,8 +10,12 @@ use bevy_wasm_scripting::*;
 use serde::Deserialize;
 
 use crate::{
-    battle::Troop,
+    battle::{CombatEvent, CombatEventKind, CombatParticipant, Faction, Troop, TroopDamaged},
+    harvest::HarvestSpot,
     loading::{AttackAssets, AudioAssets},
+    simulation::{BattleClock, BattleInterpolation},
+    structure::{damage_structure, Structure},
+    GameRng,
 };
 
 #[derive(Clone, Deserialize, TypeUuid)]
@@ -20,6 +24,41 @@ pub struct AttackType {
     pub id: i32,
     pub cooldown: f32,
     pub phases: Vec<AttackPhase>,
+    /// The troop type whose script defined it at runtime. Attacks loaded from `.attack` files
+    /// have none.
+    #[serde(skip)]
+    pub owner: Option<i32>,
+}
+
+impl AttackType {
+    /// The damage of every hit, together.
+    pub fn damage(&self) -> i32 {
+        self.phases
+            .iter()
+            .map(|phase| match phase {
+                AttackPhase::Damage { amount } => *amount,
+                _ => 0,
+            })
+            .sum()
+    }
+
+    /// Splits `amount` evenly over the hits, so `damage` reads it back. The first hits take what
+    /// doesn't divide.
+    pub fn set_damage(&mut self, amount: i32) {
+        let amount = amount.max(0);
+        let hits = self
+            .phases
+            .iter()
+            .filter(|phase| matches!(phase, AttackPhase::Damage { .. }))
+            .count() as i32;
+        let mut hit = 0;
+        for phase in self.phases.iter_mut() {
+            if let AttackPhase::Damage { amount: damage } = phase {
+                *damage = amount / hits + (hit < amount % hits) as i32;
+                hit += 1;
+            }
+        }
+    }
 }
 
 #[derive(Clone, Deserialize)]
@@ -72,6 +111,63 @@ impl AttackTypes {
     pub fn get(&self, id: i32) -> Option<AttackType> {
         self.0.get(&id).cloned()
     }
+
+    /// Copies `base_id` and its miss to `attack_id` and `-attack_id`, owned by the troop type
+    /// `owner`. Attacks loaded from files or owned by another troop type are never replaced. False
+    /// if there was nothing to copy or no room.
+    pub fn define(&mut self, base_id: i32, attack_id: i32, owner: i32) -> bool {
+        let taken = |id: i32| {
+            self.0
+                .get(&id)
+                .map_or(false, |attack| attack.owner != Some(owner))
+        };
+        // Misses are the negative ids, so a hit is needed to copy both.
+        if base_id <= 0
+            || attack_id <= 0
+            || taken(attack_id)
+            || taken(-attack_id)
+            || !self.0.contains_key(&base_id)
+        {
+            return false;
+        }
+        for (base_id, attack_id) in [(base_id, attack_id), (-base_id, -attack_id)] {
+            if let Some(mut attack_type) = self.get(base_id) {
+                attack_type.id = attack_id;
+                attack_type.owner = Some(owner);
+                self.0.insert(attack_id, attack_type);
+            }
+        }
+        true
+    }
+
+    /// Changes an attack, or its miss, that `owner` defined. False for attacks loaded from files
+    /// and those of other troop types.
+    pub fn change_scripted(
+        &mut self,
+        attack_id: i32,
+        owner: i32,
+        f: impl FnOnce(&mut AttackType),
+    ) -> bool {
+        match self.0.get_mut(&attack_id) {
+            Some(attack_type) if attack_type.owner == Some(owner) => {
+                f(attack_type);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Forgets what scripts defined, leaving the attacks loaded from files.
+    pub fn clear_scripted(&mut self) {
+        self.0.retain(|_, attack_type| attack_type.owner.is_none());
+    }
+}
+
+// A new battle starts from the attacks in files.
+pub fn clear_scripted_attacks(attack_types: Option<ResMut<AttackTypes>>) {
+    if let Some(mut attack_types) = attack_types {
+        attack_types.clear_scripted();
+    }
 }
 
 #[derive(Component)]
@@ -83,10 +179,21 @@ pub struct Attack {
     remaining_phases: Vec<AttackPhase>,
 }
 
+impl Attack {
+    pub fn attacker(&self) -> Entity {
+        self.attacker
+    }
+
+    pub fn target(&self) -> Entity {
+        self.target
+    }
+}
+
 #[derive(Bundle)]
 pub struct AttackBundle {
     sprite: SpriteSheetBundle,
     attack: Attack,
+    interpolation: BattleInterpolation,
 }
 
 pub fn spawn_attack<'a, 'b, 'c>(
@@ -109,9 +216,41 @@ pub fn spawn_attack<'a, 'b, 'c>(
             attack_type,
             phase: None,
         },
+        interpolation: BattleInterpolation::new(Vec3::ZERO),
     });
 }
 
+type AttackTroops<'w, 's> =
+    Query<'w, 's, (&'static mut Troop, &'static Transform, &'static Faction), Without<Attack>>;
+type AttackStructures<'w, 's> = Query<
+    'w,
+    's,
+    (
+        &'static mut Structure,
+        &'static GlobalTransform,
+        &'static Faction,
+        Option<&'static mut HarvestSpot>,
+    ),
+>;
+
+fn get_target_translation(
+    troops: &AttackTroops,
+    structures: &AttackStructures,
+    target: Entity,
+) -> Option<Vec3> {
+    if let Ok((_troop, transform, _faction)) = troops.get(target) {
+        Some(transform.translation)
+    } else if let Ok((structure, global, _faction, _harvest_spot)) = structures.get(target) {
+        if structure.is_destroyed() {
+            None
+        } else {
+            Some(global.translation())
+        }
+    } else {
+        None
+    }
+}
+
 pub fn attack_phase_system(
     mut commands: Commands,
     mut attacks: Query<(
@@ -120,30 +259,38 @@ pub fn attack_phase_system(
         &mut TextureAtlasSprite,
         &mut Transform,
         &mut Visibility,
+        &mut BattleInterpolation,
     )>,
-    mut troops: Query<(&mut Troop, &GlobalTransform)>,
-    audio_assets: Res<AudioAssets>,
-    audio: Res<Audio>,
-    time: Res<Time>,
+    mut troops: AttackTroops,
+    mut structures: AttackStructures,
+    mut combat_events: EventWriter<CombatEvent>,
+    mut damage_events: EventWriter<TroopDamaged>,
+    audio_assets: Option<Res<AudioAssets>>,
+    audio: Option<Res<Audio>>,
+    mut rng: ResMut<GameRng>,
+    clock: Res<BattleClock>,
 ) {
-    let delta_seconds = time.delta_seconds();
-    for (entity, mut attack, sprite, transform, visibility) in attacks.iter_mut() {
-        let target = troops.get_mut(attack.target);
-        if target.is_err() {
+    let delta_seconds = clock.delta_seconds();
+    for (entity, attack, _sprite, _transform, _visibility, _interpolation) in attacks.iter() {
+        if get_target_translation(&troops, &structures, attack.target).is_none() {
             if let Some(mut entity) = commands.get_entity(entity) {
                 entity.despawn();
             }
         }
     }
-    for (entity, mut attack, mut sprite, mut transform, mut visibility) in attacks.iter_mut() {
+    for (entity, mut attack, mut sprite, mut transform, mut visibility, mut interpolation) in
+        attacks.iter_mut()
+    {
         let target = attack.target.clone();
+        let attacker = attack.attacker.clone();
+        let attack_id = attack.attack_type.id;
         match &mut attack.phase {
             Some(AttackPhase::Projectile {
                 sprite_index,
                 speed,
             }) => {
-                if let Ok(target) = troops.get(target) {
-                    let delta = target.1.translation() - transform.translation;
+                if let Some(target) = get_target_translation(&troops, &structures, target) {
+                    let delta = target - transform.translation;
                     if delta.length_squared() < delta_seconds * delta_seconds * *speed * *speed {
                         attack.phase = None;
                     } else {
@@ -163,8 +310,45 @@ pub fn attack_phase_system(
                 }
             }
             Some(AttackPhase::Damage { amount }) => {
+                let source = troops
+                    .get(attacker)
+                    .ok()
+                    .map(|(troop, _global, faction)| CombatParticipant::new(troop, faction));
+                let damaged = TroopDamaged {
+                    target,
+                    attacker,
+                    amount: *amount,
+                };
                 if let Ok(mut target) = troops.get_mut(target) {
                     target.0.health -= *amount;
+                    target.0.last_attacker = Some(attacker);
+                    combat_events.send(CombatEvent {
+                        time: clock.elapsed_seconds(),
+                        kind: CombatEventKind::Hit {
+                            attack_id,
+                            amount: *amount,
+                        },
+                        source,
+                        target: CombatParticipant::new(&target.0, target.2),
+                    });
+                    damage_events.send(damaged);
+                } else if let Ok((mut structure, _global, faction, harvest_spot)) =
+                    structures.get_mut(target)
+                {
+                    damage_structure(
+                        &mut structure,
+                        harvest_spot.map(|harvest_spot| harvest_spot.into_inner()),
+                        *amount,
+                    );
+                    combat_events.send(CombatEvent {
+                        time: clock.elapsed_seconds(),
+                        kind: CombatEventKind::Hit {
+                            attack_id,
+                            amount: *amount,
+                        },
+                        source,
+                        target: CombatParticipant::structure(faction),
+                    });
                 }
                 attack.phase = None;
             }
@@ -175,10 +359,12 @@ pub fn attack_phase_system(
                 }
             }
             Some(AttackPhase::PickSound { options }) => {
-                let index = (rand::random::<f32>() * options.len() as f32).floor() as usize;
+                let index = (rng.random() * options.len() as f32).floor() as usize;
                 let index = options[index];
-                let sound = audio_assets.collection[index].clone();
-                audio.play(sound);
+                // Headless battles, like balance sweeps, run without audio.
+                if let (Some(audio_assets), Some(audio)) = (&audio_assets, &audio) {
+                    audio.play(audio_assets.collection[index].clone());
+                }
                 attack.phase = None;
             }
             None => {}
@@ -191,10 +377,11 @@ pub fn attack_phase_system(
                         sprite_index,
                         speed,
                     } => {
-                        if let Ok((_troop, my_global)) = troops.get(attack.attacker) {
-                            transform.translation.x = my_global.translation().x;
-                            transform.translation.y = my_global.translation().y;
+                        if let Ok((_troop, my_transform, _faction)) = troops.get(attack.attacker) {
+                            transform.translation.x = my_transform.translation.x;
+                            transform.translation.y = my_transform.translation.y;
                             transform.translation.z = 20.;
+                            interpolation.snap(transform.translation);
                         }
                         sprite.index = *sprite_index;
                         *visibility = Visibility::VISIBLE;
@@ -203,11 +390,14 @@ pub fn attack_phase_system(
                         sprite_index,
                         duration,
                     } => {
-                        if let Ok((_troop, my_global)) = troops.get(attack.target) {
-                            transform.translation.x = my_global.translation().x;
-                            transform.translation.y = my_global.translation().y;
+                        if let Some(target) =
+                            get_target_translation(&troops, &structures, attack.target)
+                        {
+                            transform.translation.x = target.x;
+                            transform.translation.y = target.y;
                             transform.translation.z = 20.;
                             transform.rotation = Quat::default();
+                            interpolation.snap(transform.translation);
                         }
                         sprite.index = *sprite_index;
                         *visibility = Visibility::VISIBLE;
@@ -230,3 +420,82 @@ pub fn attack_phase_system(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attack(id: i32, amount: i32) -> AttackType {
+        AttackType {
+            id,
+            cooldown: 1.,
+            phases: vec![AttackPhase::Damage { amount }],
+            owner: None,
+        }
+    }
+
+    #[test]
+    fn damage_is_split_over_the_hits() {
+        let mut attack_type = attack(1, 2);
+        attack_type
+            .phases
+            .push(AttackPhase::Hidden { duration: 0.1 });
+        attack_type.phases.push(AttackPhase::Damage { amount: 2 });
+        assert_eq!(attack_type.damage(), 4);
+
+        attack_type.set_damage(7);
+        assert_eq!(attack_type.damage(), 7);
+        assert!(matches!(
+            attack_type.phases[0],
+            AttackPhase::Damage { amount: 4 }
+        ));
+        attack_type.set_damage(-3);
+        assert_eq!(attack_type.damage(), 0);
+    }
+
+    const SOLDIER: i32 = 0;
+    const ARCHER: i32 = 1;
+
+    #[test]
+    fn scripts_define_their_own_attacks() {
+        let mut attack_types = AttackTypes(HashMap::from_iter([
+            (1, attack(1, 2)),
+            (-1, attack(-1, 0)),
+            (2, attack(2, 3)),
+        ]));
+
+        assert!(attack_types.define(1, 10, SOLDIER));
+        assert_eq!(attack_types.0[&10].owner, Some(SOLDIER));
+        assert_eq!(attack_types.0[&-10].id, -10);
+        assert!(attack_types.define(2, 10, SOLDIER));
+        assert!(!attack_types.define(1, 10, ARCHER));
+        assert!(!attack_types.define(1, 2, SOLDIER));
+        assert!(!attack_types.define(-1, 11, SOLDIER));
+        assert!(!attack_types.define(1, -11, SOLDIER));
+        assert!(!attack_types.define(3, 11, SOLDIER));
+    }
+
+    #[test]
+    fn only_the_owner_changes_an_attack() {
+        let mut attack_types = AttackTypes(HashMap::from_iter([(1, attack(1, 2))]));
+        attack_types.define(1, 10, SOLDIER);
+
+        assert!(attack_types.change_scripted(10, SOLDIER, |attack_type| attack_type.cooldown = 0.5));
+        assert!(!attack_types.change_scripted(10, ARCHER, |attack_type| attack_type.cooldown = 0.));
+        assert!(!attack_types.change_scripted(1, SOLDIER, |attack_type| attack_type.cooldown = 0.));
+        assert_eq!(attack_types.0[&10].cooldown, 0.5);
+        assert_eq!(attack_types.0[&1].cooldown, 1.);
+    }
+
+    #[test]
+    fn clearing_keeps_the_attacks_from_files() {
+        let mut attack_types =
+            AttackTypes(HashMap::from_iter([(1, attack(1, 2)), (-1, attack(-1, 0))]));
+        attack_types.define(1, 10, SOLDIER);
+
+        attack_types.clear_scripted();
+        let mut ids: Vec<i32> = attack_types.0.keys().copied().collect();
+        ids.sort();
+        assert_eq!(ids, vec![-1, 1]);
+    }
+}
@@ -0,0 +1,193 @@
+use std::fmt::Write;
+
+use bevy::{log::LogPlugin, prelude::*, utils::HashMap};
+use serde::Deserialize;
+
+use crate::{
+    battle::{Faction, StagingLocation, Troop},
+    headless::HeadlessBattle,
+    wave::{CurrentWave, Wave, Waves, SPAWN_TIME},
+    GameRng,
+};
+
+/// Set this environment variable to a `.sweep` file to run a balance sweep instead of a game.
+pub const BALANCE_SWEEP_ENV: &str = "HARVESTS_BALANCE_SWEEP";
+
+/// Runs every trial in the `.sweep` file at `path` in a headless battle, then writes the report.
+pub fn run_balance_sweep(path: &str) -> anyhow::Result<()> {
+    let config = ron::de::from_str::<BalanceSweepConfig>(&std::fs::read_to_string(path)?)?;
+    let mut app = App::new();
+    app.add_plugin(LogPlugin::default());
+    let mut battle = HeadlessBattle::new(app)?;
+    info!("Running balance sweep from {}", path);
+    let mut sweep = BalanceSweep::new(config, battle.world().resource::<Waves>());
+    for trial in 0..sweep.trial_count() {
+        let result = sweep.run_trial(&mut battle, trial);
+        sweep.results.push(result);
+    }
+    let report = sweep.report();
+    info!("Balance sweep finished:\n{}", report);
+    std::fs::write(&sweep.config.output, report)?;
+    Ok(())
+}
+
+#[derive(Deserialize)]
+pub struct BalanceSweepConfig {
+    /// Each army is a list of troop ids and buffs, as accepted by `stage_with_buffs`.
+    pub armies: Vec<Vec<(i32, HashMap<i32, f32>)>>,
+    /// Wave ids to fight. Every authored wave is used if this is left out.
+    #[serde(default)]
+    pub waves: Vec<i32>,
+    pub runs: usize,
+    #[serde(default)]
+    pub seed: u64,
+    pub time_limit: f32,
+    pub output: String,
+}
+
+struct TrialResult {
+    army: usize,
+    wave_id: i32,
+    won: bool,
+    time: f32,
+    surviving_health: i32,
+    score: f32,
+}
+
+struct BalanceSweep {
+    config: BalanceSweepConfig,
+    waves: Vec<i32>,
+    results: Vec<TrialResult>,
+}
+
+impl BalanceSweep {
+    fn new(config: BalanceSweepConfig, waves: &Waves) -> Self {
+        let mut wave_ids = config.waves.clone();
+        if wave_ids.is_empty() {
+            wave_ids = waves.0.keys().filter(|id| **id >= 0).cloned().collect();
+            wave_ids.sort();
+        }
+        Self {
+            config,
+            waves: wave_ids,
+            results: Vec::new(),
+        }
+    }
+
+    fn trial_count(&self) -> usize {
+        self.config.armies.len() * self.waves.len() * self.config.runs
+    }
+
+    fn trial_army(&self, trial: usize) -> usize {
+        trial / (self.waves.len() * self.config.runs)
+    }
+
+    fn trial_wave(&self, trial: usize) -> i32 {
+        self.waves[(trial / self.config.runs) % self.waves.len()]
+    }
+
+    fn trial_seed(&self, trial: usize) -> u64 {
+        self.config.seed.wrapping_add(trial as u64)
+    }
+
+    /// Stages the trial's army in the middle of the map, where the king would stand, and steps
+    /// the battle until one side is gone or time runs out.
+    fn run_trial(&self, battle: &mut HeadlessBattle, trial: usize) -> TrialResult {
+        battle.reset();
+        let world = battle.world();
+        world
+            .resource_mut::<GameRng>()
+            .reseed(self.trial_seed(trial));
+        let wave = world.resource::<Waves>().get(self.trial_wave(trial));
+        world
+            .resource_mut::<CurrentWave>()
+            .set_wave(wave.clone(), -1.);
+        let staging = battle.staging_spot(Vec2::ZERO);
+        let mut staging = battle.world().get_mut::<StagingLocation>(staging).unwrap();
+        for (troop_id, buffs) in self.config.armies[self.trial_army(trial)].iter() {
+            staging.stage_with_buffs(*troop_id, buffs.clone());
+        }
+
+        let mut deployed = false;
+        loop {
+            battle.step();
+            let world = battle.world();
+            let mut player_troops = 0;
+            let mut surviving_health = 0;
+            let mut enemy_troops = 0;
+            for (troop, faction) in world.query::<(&Troop, &Faction)>().iter(world) {
+                if faction.faction_id == Faction::player().faction_id {
+                    player_troops += 1;
+                    surviving_health += troop.health.max(0);
+                } else {
+                    enemy_troops += 1;
+                }
+            }
+            deployed |= player_troops > 0;
+            let time = world.resource::<CurrentWave>().time_in_wave;
+            if deployed && player_troops == 0 {
+                return self.result(trial, false, time, 0, &wave);
+            } else if deployed && enemy_troops == 0 && time > SPAWN_TIME + 2. {
+                return self.result(trial, true, time, surviving_health, &wave);
+            } else if time > self.config.time_limit {
+                return self.result(trial, false, time, surviving_health, &wave);
+            }
+        }
+    }
+
+    fn result(
+        &self,
+        trial: usize,
+        won: bool,
+        time: f32,
+        surviving_health: i32,
+        wave: &Wave,
+    ) -> TrialResult {
+        TrialResult {
+            army: self.trial_army(trial),
+            wave_id: wave.id,
+            won,
+            time,
+            surviving_health,
+            score: if won { wave.score(time) } else { 0. },
+        }
+    }
+
+    fn report(&self) -> String {
+        let mut report = String::from(
+            "army,wave,runs,win_rate,mean_time_to_clear,mean_surviving_health,mean_score\n",
+        );
+        for army in 0..self.config.armies.len() {
+            for wave_id in self.waves.iter() {
+                let results: Vec<&TrialResult> = self
+                    .results
+                    .iter()
+                    .filter(|result| result.army == army && result.wave_id == *wave_id)
+                    .collect();
+                let runs = results.len().max(1) as f32;
+                let wins: Vec<&&TrialResult> = results.iter().filter(|result| result.won).collect();
+                let mean_time = if wins.is_empty() {
+                    f32::NAN
+                } else {
+                    wins.iter().map(|result| result.time).sum::<f32>() / wins.len() as f32
+                };
+                let _ = writeln!(
+                    report,
+                    "{},{},{},{},{},{},{}",
+                    army,
+                    wave_id,
+                    results.len(),
+                    wins.len() as f32 / runs,
+                    mean_time,
+                    results
+                        .iter()
+                        .map(|result| result.surviving_health as f32)
+                        .sum::<f32>()
+                        / runs,
+                    results.iter().map(|result| result.score).sum::<f32>() / runs,
+                );
+            }
+        }
+        report
+    }
+}
@@ -6,16 +6,26 @@ use bevy::{
     utils::HashMap,
 };
 use bevy_wasm_scripting::*;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 use crate::{
-    attacks::{attack_phase_system, AttackAssetLoader, AttackType},
-    common_scripting::ScriptValues,
+    attacks::{attack_phase_system, clear_scripted_attacks, AttackAssetLoader, AttackType},
+    common_scripting::{
+        compile_script, disable_export, quarantine, DisabledExports, Quarantined, ScriptMemory,
+        ScriptValues, WatScriptLoader,
+    },
     delivery::*,
     harvest::spawn_harvest_spot,
     helper::HelperTextBundle,
+    king::KingAbility,
     loading::*,
-    GameState, SafeInsert,
+    script_abi::{validate_script, ScriptRole},
+    script_profiler::{profiled, ScriptProfile},
+    script_scope::{with_scope, ScriptCategory},
+    simulation::{BattleClock, BattleInterpolation, BattleStage},
+    structure::Structure,
+    wave::{wave_spawning_system, WaveStarted},
+    GameRng, GameState, SafeInsert,
 };
 
 pub struct BattlePlugin;
@@ -24,41 +34,86 @@ impl Plugin for BattlePlugin {
     fn build(&self, app: &mut App) {
         app.add_asset::<TroopType>()
             .init_asset_loader::<TroopAssetLoader>()
+            .init_asset_loader::<WatScriptLoader>()
+            .add_event::<CombatEvent>()
+            .init_resource::<Events<TroopSpawned>>()
+            .init_resource::<Events<TroopDamaged>>()
+            .init_resource::<Events<TroopKilled>>()
             .add_asset::<AttackType>()
             .init_asset_loader::<AttackAssetLoader>()
             .add_system_set(SystemSet::on_enter(GameState::Playing).with_system(spawn_king))
             .add_system_set(
                 SystemSet::on_enter(GameState::Playing).with_system(spawn_player_staging_spot),
             )
-            .add_system_set(SystemSet::on_update(GameState::Playing).with_system(
-                ScriptSystemWithCommands::<_, Troop>::wrap(IntoSystem::into_system(
-                    troop_battle_action_system,
-                )),
-            ))
-            .add_system_set(SystemSet::on_update(GameState::Playing).with_system(
-                ScriptSystemWithCommands::<_, Troop>::wrap(IntoSystem::into_system(
-                    troop_death_system,
-                )),
-            ))
-            .add_system_set(
-                SystemSet::on_update(GameState::Playing).with_system(attack_phase_system),
-            )
-            .add_system_set(
-                SystemSet::on_update(GameState::Playing).with_system(troop_staging_system),
-            )
             .add_system_set(
-                SystemSet::on_update(GameState::Playing).with_system(troop_cooldown_system),
-            )
-            .add_system_set(
-                SystemSet::on_update(GameState::Playing).with_system(troop_movement_system),
-            )
-            .add_system_set(
-                SystemSet::on_update(GameState::Playing).with_system(troop_restitution_system),
+                SystemSet::on_exit(GameState::Playing).with_system(clear_scripted_attacks),
             )
+            .add_system_set_to_stage(BattleStage, troop_systems())
             .add_wasm_script_component::<Troop>();
     }
 }
 
+#[derive(Debug, Hash, PartialEq, Eq, Clone, SystemLabel)]
+pub enum TroopSystem {
+    Deaths,
+    Hooks,
+}
+
+/// Everything troops do in a battle step. The headless battle runs these without the rest of the
+/// plugin.
+///
+/// The hook events are sent and read within a step, and a frame can run several steps or none,
+/// so they are cleared after every step instead of every frame.
+pub fn troop_systems() -> SystemSet {
+    SystemSet::on_update(GameState::Playing)
+        .with_system(ScriptSystemWithCommands::<_, Troop>::wrap(
+            IntoSystem::into_system(troop_battle_action_system),
+        ))
+        .with_system(
+            ScriptSystemWithCommands::<_, Troop>::wrap(IntoSystem::into_system(troop_death_system))
+                .label(TroopSystem::Deaths),
+        )
+        // Staged troops only exist once the step that spawned them is over, so they get their
+        // `on_spawn` at the start of the next one.
+        .with_system(
+            ScriptSystemWithCommands::<_, Troop>::wrap(IntoSystem::into_system(
+                troop_spawn_hook_system,
+            ))
+            .label(TroopSystem::Hooks)
+            .before(troop_staging_system),
+        )
+        .with_system(
+            ScriptSystemWithCommands::<_, Troop>::wrap(IntoSystem::into_system(
+                troop_damaged_hook_system,
+            ))
+            .label(TroopSystem::Hooks)
+            .after(attack_phase_system),
+        )
+        .with_system(
+            ScriptSystemWithCommands::<_, Troop>::wrap(IntoSystem::into_system(
+                troop_kill_hook_system,
+            ))
+            .label(TroopSystem::Hooks)
+            .after(TroopSystem::Deaths),
+        )
+        .with_system(
+            ScriptSystemWithCommands::<_, Troop>::wrap(IntoSystem::into_system(
+                troop_wave_start_hook_system,
+            ))
+            .label(TroopSystem::Hooks)
+            .after(wave_spawning_system),
+        )
+        .with_system(Events::<TroopSpawned>::update_system.after(TroopSystem::Hooks))
+        .with_system(Events::<TroopDamaged>::update_system.after(TroopSystem::Hooks))
+        .with_system(Events::<TroopKilled>::update_system.after(TroopSystem::Hooks))
+        .with_system(Events::<WaveStarted>::update_system.after(TroopSystem::Hooks))
+        .with_system(attack_phase_system)
+        .with_system(troop_staging_system)
+        .with_system(troop_cooldown_system)
+        .with_system(troop_movement_system)
+        .with_system(troop_restitution_system)
+}
+
 #[derive(Bundle)]
 pub struct StagingBundle {
     sprite: SpriteSheetBundle,
@@ -66,6 +121,7 @@ pub struct StagingBundle {
     staging_location: StagingLocation,
     delivery_dropoff: DeliveryDropoff,
     delivery_anchor: DeliveryAnchor,
+    structure: Structure,
 }
 
 #[derive(Component, Default)]
@@ -110,6 +166,7 @@ fn spawn_player_staging_spot(
                 staging_location: Default::default(),
                 delivery_anchor: DeliveryAnchor::new(0., -16., 32., 64 * 64),
                 delivery_dropoff: DeliveryDropoff::new(scripts.staging.clone()),
+                structure: Structure::new(15),
             })
             .add_child(helper);
     }
@@ -128,7 +185,7 @@ fn spawn_king(
         fonts.fira_sans.clone(),
         textures.troops.clone(),
         delivery_scripts.deliver_king.clone(),
-        troop_types.get(87).unwrap(),
+        troop_types.get(KING_ID).unwrap(),
         Faction::player(),
         HashMap::default(),
     );
@@ -142,6 +199,8 @@ fn spawn_king(
     });
 }
 
+pub const KING_ID: i32 = 87;
+
 #[derive(Clone, Deserialize, TypeUuid)]
 #[uuid = "57cde8f9-c5e6-4a79-988d-214c3ea1df8e"]
 pub struct TroopType {
@@ -150,9 +209,39 @@ pub struct TroopType {
     pub health: i32,
     pub sprite_index: usize,
     pub size: f32,
+    #[serde(default = "default_move_speed")]
+    pub move_speed: f32,
+    #[serde(default = "default_attack_range")]
+    pub attack_range: f32,
     pub script_path: String,
+    #[serde(default)]
+    pub abilities: Vec<KingAbility>,
     #[serde(skip_deserializing)]
     pub script: Option<Handle<WasmScript>>,
+    /// Names exported by the script, so optional hooks are only called when present.
+    #[serde(skip_deserializing)]
+    pub exports: Vec<String>,
+}
+
+// Every troop moved at 32 and melee troops hit from 36 before these were declared per type, so
+// `.troop` files that leave them out behave as they used to.
+fn default_move_speed() -> f32 {
+    32.
+}
+
+fn default_attack_range() -> f32 {
+    36.
+}
+
+impl TroopType {
+    /// Troops without a move speed, like towers, ignore movement and are never pushed around.
+    pub fn is_immobile(&self) -> bool {
+        self.move_speed <= 0.
+    }
+
+    pub fn has_export(&self, name: &str) -> bool {
+        self.exports.iter().any(|export| export == name)
+    }
 }
 
 #[derive(Default)]
@@ -167,12 +256,16 @@ impl AssetLoader for TroopAssetLoader {
         Box::pin(async move {
             let mut custom_asset = ron::de::from_bytes::<TroopType>(bytes)?;
             let script_path = custom_asset.script_path.clone();
-            let wasm_bytes = load_context.read_asset_bytes(script_path).await?;
+            let wasm_bytes = compile_script(
+                &script_path,
+                load_context.read_asset_bytes(&script_path).await?,
+            )?;
+            custom_asset.exports = validate_script(&script_path, ScriptRole::Troop, &wasm_bytes)?;
             custom_asset.script = Some(load_context.set_labeled_asset(
                 "battle_script",
                 LoadedAsset::new(WasmScript::Loaded(
                     format!("troop:{}", custom_asset.id),
-                    wasm_bytes.to_vec(),
+                    wasm_bytes,
                 )),
             ));
             load_context.set_default_asset(LoadedAsset::new(custom_asset));
@@ -215,6 +308,9 @@ pub struct Troop {
     pub staging_point: Vec2,
     pub target: Option<(Vec2, f32)>,
     pub seen_troops: Vec<Entity>,
+    pub seen_structures: Vec<Entity>,
+    pub seen_allies: Vec<Entity>,
+    pub last_attacker: Option<Entity>,
 }
 
 impl Troop {
@@ -225,12 +321,23 @@ impl Troop {
             staging_point,
             target: None,
             seen_troops: Vec::new(),
+            seen_structures: Vec::new(),
+            seen_allies: Vec::new(),
+            last_attacker: None,
         }
     }
 
     pub fn scan(&mut self, seen_troops: Vec<Entity>) {
         self.seen_troops = seen_troops;
     }
+
+    pub fn scan_structures(&mut self, seen_structures: Vec<Entity>) {
+        self.seen_structures = seen_structures;
+    }
+
+    pub fn scan_allies(&mut self, seen_allies: Vec<Entity>) {
+        self.seen_allies = seen_allies;
+    }
 }
 
 #[derive(Component, Copy, Clone)]
@@ -259,6 +366,97 @@ impl Faction {
 #[derive(Component)]
 pub struct TroopCooldown(pub f32);
 
+#[derive(Clone, Copy, Serialize)]
+pub struct CombatParticipant {
+    /// Missing for structures.
+    pub troop_type_id: Option<i32>,
+    pub faction_id: i32,
+}
+
+impl CombatParticipant {
+    pub fn new(troop: &Troop, faction: &Faction) -> Self {
+        Self {
+            troop_type_id: Some(troop.troop_type.id),
+            faction_id: faction.faction_id,
+        }
+    }
+
+    pub fn structure(faction: &Faction) -> Self {
+        Self {
+            troop_type_id: None,
+            faction_id: faction.faction_id,
+        }
+    }
+
+    pub fn of(world: &World, entity: Entity) -> Option<Self> {
+        if let (Some(troop), Some(faction)) =
+            (world.get::<Troop>(entity), world.get::<Faction>(entity))
+        {
+            Some(Self::new(troop, faction))
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum CombatEventKind {
+    Attack { attack_id: i32, dodged: bool },
+    Hit { attack_id: i32, amount: i32 },
+    Heal { amount: i32 },
+    Buff { script_value_id: i32, value: f32 },
+    Death,
+}
+
+// Participants are captured when the event is sent, since troops are often despawned before
+// anything reads the event. Times are on the battle clock, so a seeded battle logs the same times.
+#[derive(Clone, Serialize)]
+pub struct CombatEvent {
+    pub time: f64,
+    #[serde(flatten)]
+    pub kind: CombatEventKind,
+    pub source: Option<CombatParticipant>,
+    pub target: CombatParticipant,
+}
+
+/// Feeds the `on_spawn` hook of a troop staged into battle.
+pub struct TroopSpawned {
+    pub troop: Entity,
+}
+
+/// Feeds the `on_damaged` hook of the troop that was hit.
+pub struct TroopDamaged {
+    pub target: Entity,
+    pub attacker: Entity,
+    pub amount: i32,
+}
+
+/// Feeds the `on_kill` hook of the troop that landed the last hit.
+pub struct TroopKilled {
+    pub killer: Entity,
+    pub victim: Entity,
+}
+
+pub fn send_combat_event(
+    world: &mut World,
+    kind: CombatEventKind,
+    source: Option<CombatParticipant>,
+    target: CombatParticipant,
+) {
+    let time = world
+        .get_resource::<BattleClock>()
+        .map_or(0., |clock| clock.elapsed_seconds());
+    if let Some(mut events) = world.get_resource_mut::<Events<CombatEvent>>() {
+        events.send(CombatEvent {
+            time,
+            kind,
+            source,
+            target,
+        });
+    }
+}
+
 #[derive(Bundle)]
 pub struct FactionIndicatorBundle {
     sprite: SpriteSheetBundle,
@@ -271,8 +469,10 @@ pub struct TroopBundle {
     troop: Troop,
     faction: Faction,
     script_values: ScriptValues,
+    script_memory: ScriptMemory,
     delivery_dropoff: DeliveryDropoff,
     delivery_anchor: DeliveryAnchor,
+    interpolation: BattleInterpolation,
 }
 
 pub fn spawn_troop<'a, 'b, 'c>(
@@ -284,7 +484,7 @@ pub fn spawn_troop<'a, 'b, 'c>(
     troop: TroopType,
     faction: Faction,
     buffs: HashMap<i32, f32>,
-) {
+) -> Entity {
     let faction_indicator = commands
         .spawn(FactionIndicatorBundle {
             sprite: SpriteSheetBundle {
@@ -320,22 +520,25 @@ pub fn spawn_troop<'a, 'b, 'c>(
             faction,
             troop: Troop::new(troop, position),
             script_values: ScriptValues(buffs),
+            script_memory: Default::default(),
             delivery_dropoff: DeliveryDropoff {
                 script: dropoff_script.clone(),
             },
             delivery_anchor: DeliveryAnchor::new(0., -4., 8., 20 * 20),
+            interpolation: BattleInterpolation::new(Vec3::new(position.x, position.y, 1.)),
         })
         .add_child(faction_indicator)
-        .add_child(helper);
+        .add_child(helper)
+        .id()
 }
 
 fn troop_cooldown_system(
     mut commands: Commands,
     mut troops_with_cooldowns: Query<(Entity, &mut TroopCooldown)>,
-    time: Res<Time>,
+    clock: Res<BattleClock>,
 ) {
     for (entity, mut troop) in troops_with_cooldowns.iter_mut() {
-        troop.0 -= time.delta_seconds();
+        troop.0 -= clock.delta_seconds();
         if troop.0 < 0. {
             if let Some(mut entity) = commands.get_entity(entity) {
                 entity.remove::<TroopCooldown>();
@@ -344,17 +547,22 @@ fn troop_cooldown_system(
     }
 }
 
+// Troops are never parented, so the simulation works on Transform alone. GlobalTransform is only
+// propagated once per frame, which may be several steps.
 fn troop_movement_system(
-    mut troops: Query<(&GlobalTransform, &mut Transform, &mut Troop)>,
-    time: Res<Time>,
+    mut troops: Query<(&mut Transform, &mut Troop, Option<&Quarantined>)>,
+    clock: Res<BattleClock>,
 ) {
-    for (global, mut transform, mut troop) in troops.iter_mut() {
+    let delta_seconds = clock.delta_seconds();
+    for (mut transform, mut troop, quarantined) in troops.iter_mut() {
+        if troop.troop_type.is_immobile() || quarantined.is_some() {
+            troop.target = None;
+            continue;
+        }
         if let Some((target, speed)) = troop.target {
-            let delta = target - Vec2::new(global.translation().x, global.translation().y);
-            if delta.length_squared()
-                > (speed * speed * time.delta_seconds() * time.delta_seconds())
-            {
-                let travel = delta.normalize() * time.delta_seconds() * speed;
+            let delta = target - Vec2::new(transform.translation.x, transform.translation.y);
+            if delta.length_squared() > (speed * speed * delta_seconds * delta_seconds) {
+                let travel = delta.normalize() * delta_seconds * speed;
                 transform.translation.x += travel.x;
                 transform.translation.y += travel.y;
             } else {
@@ -364,46 +572,62 @@ fn troop_movement_system(
     }
 }
 
-fn troop_restitution_system(
-    mut troops: Query<(&GlobalTransform, &mut Transform, &Troop)>,
-    time: Res<Time>,
-) {
+fn troop_restitution_system(mut troops: Query<(&mut Transform, &Troop)>, clock: Res<BattleClock>) {
     let mut iter = troops.iter_combinations_mut();
-    while let Some([(global_a, mut transform_a, troop_a), (global_b, mut transform_b, troop_b)]) =
-        iter.fetch_next()
-    {
-        let delta = global_b.translation() - global_a.translation();
+    while let Some([(mut transform_a, troop_a), (mut transform_b, troop_b)]) = iter.fetch_next() {
+        let delta = transform_b.translation - transform_a.translation;
         let distance = delta.length();
         let size_bar = troop_a.troop_type.size + troop_b.troop_type.size;
 
         if distance > 0. && distance < size_bar {
             let restitution = delta.normalize() * (distance - size_bar) / 2.;
-            restitution.clamp_length_max(time.delta_seconds() * 16.);
-            transform_a.translation += restitution;
-            transform_b.translation -= restitution;
+            restitution.clamp_length_max(clock.delta_seconds() * 16.);
+            // Whoever can move takes the whole push when the other can't.
+            match (
+                troop_a.troop_type.is_immobile(),
+                troop_b.troop_type.is_immobile(),
+            ) {
+                (false, false) => {
+                    transform_a.translation += restitution;
+                    transform_b.translation -= restitution;
+                }
+                (false, true) => transform_a.translation += restitution * 2.,
+                (true, false) => transform_b.translation -= restitution * 2.,
+                (true, true) => {}
+            }
         }
     }
 }
 
-fn troop_battle_action_system(
+pub fn troop_battle_action_system(
     mut commands: Commands,
     mut script_env: WasmScriptComponentEnv<Troop, ()>,
-    troops: Query<(Entity, &Troop), Without<TroopCooldown>>,
+    mut profile: Option<ResMut<ScriptProfile>>,
+    troops: Query<(Entity, &Troop), (Without<TroopCooldown>, Without<Quarantined>)>,
 ) {
     for (entity, troop) in troops.iter() {
-        match script_env.call_if_instantiated_1::<f64, f32>(
-            troop.get_wasm_script_handle(),
-            "battle_action",
-            EntityId::from_entity(entity),
-        ) {
+        let script = troop.get_wasm_script_handle();
+        match profiled(&mut profile, script, "battle_action", || {
+            with_scope(ScriptCategory::Battle, &[entity], || {
+                script_env.call_if_instantiated_1::<f64, f32>(
+                    script,
+                    "battle_action",
+                    EntityId::from_entity(entity),
+                )
+            })
+        }) {
             Ok(cooldown) => {
                 if cooldown > 0. {
                     commands.add(SafeInsert::new(entity, TroopCooldown(cooldown)));
                 }
             }
-            Err(err) => {
-                error!("Could not execute battle action: {}", err);
-            }
+            Err(err) => quarantine(
+                &mut commands,
+                entity,
+                troop.get_wasm_script_handle(),
+                "battle_action",
+                err,
+            ),
         }
     }
 }
@@ -411,25 +635,191 @@ fn troop_battle_action_system(
 fn troop_death_system(
     mut commands: Commands,
     mut script_env: WasmScriptComponentEnv<Troop, ()>,
-    troops: Query<(Entity, &Troop)>,
+    mut combat_events: EventWriter<CombatEvent>,
+    mut kill_events: EventWriter<TroopKilled>,
+    mut profile: Option<ResMut<ScriptProfile>>,
+    troops: Query<(Entity, &Troop, &Faction, Option<&Quarantined>)>,
+    clock: Res<BattleClock>,
 ) {
-    for (entity, troop) in troops.iter() {
+    for (entity, troop, faction, quarantined) in troops.iter() {
         if troop.health <= 0 {
-            match script_env.call_if_instantiated_1::<f64, i8>(
-                troop.get_wasm_script_handle(),
-                "on_death",
-                EntityId::from_entity(entity),
-            ) {
+            // Troops with a broken script can't hold on to life.
+            let dies = if quarantined.is_some() {
+                Ok(1)
+            } else {
+                let script = troop.get_wasm_script_handle();
+                profiled(&mut profile, script, "on_death", || {
+                    with_scope(ScriptCategory::Battle, &[entity], || {
+                        script_env.call_if_instantiated_1::<f64, i8>(
+                            script,
+                            "on_death",
+                            EntityId::from_entity(entity),
+                        )
+                    })
+                })
+            };
+            match dies {
                 Ok(dies) => {
                     if dies == 1 {
+                        combat_events.send(CombatEvent {
+                            time: clock.elapsed_seconds(),
+                            kind: CombatEventKind::Death,
+                            source: None,
+                            target: CombatParticipant::new(troop, faction),
+                        });
+                        if let Some(killer) = troop.last_attacker {
+                            kill_events.send(TroopKilled {
+                                killer,
+                                victim: entity,
+                            });
+                        }
                         if let Some(mut entity) = commands.get_entity(entity) {
                             entity.despawn_recursive();
                         }
                     }
                 }
-                Err(err) => {
-                    error!("Could not execute on_death: {}", err);
-                }
+                Err(err) => quarantine(
+                    &mut commands,
+                    entity,
+                    troop.get_wasm_script_handle(),
+                    "on_death",
+                    err,
+                ),
+            }
+        }
+    }
+}
+
+// The hooks below are optional exports, so scripts only implement the ones they react to. A hook
+// that fails is disabled on its own, leaving the troop's `battle_action` running.
+fn hook_enabled(troop: &Troop, disabled: Option<&DisabledExports>, export: &str) -> bool {
+    troop.troop_type.has_export(export) && DisabledExports::allows(disabled, export)
+}
+
+fn troop_spawn_hook_system(
+    mut commands: Commands,
+    mut script_env: WasmScriptComponentEnv<Troop, ()>,
+    mut spawn_events: EventReader<TroopSpawned>,
+    troops: Query<(&Troop, Option<&DisabledExports>), Without<Quarantined>>,
+) {
+    for event in spawn_events.iter() {
+        let entity = event.troop;
+        let troop = if let Ok((troop, disabled)) = troops.get(entity) {
+            if !hook_enabled(troop, disabled, "on_spawn") {
+                continue;
+            }
+            troop
+        } else {
+            continue;
+        };
+        if let Err(err) = with_scope(ScriptCategory::Battle, &[entity], || {
+            script_env.call_if_instantiated_1::<f64, ()>(
+                troop.get_wasm_script_handle(),
+                "on_spawn",
+                EntityId::from_entity(entity),
+            )
+        }) {
+            disable_export(
+                &mut commands,
+                entity,
+                troop.get_wasm_script_handle(),
+                "on_spawn",
+                err,
+            );
+        }
+    }
+}
+
+pub fn troop_damaged_hook_system(
+    mut commands: Commands,
+    mut script_env: WasmScriptComponentEnv<Troop, ()>,
+    mut damage_events: EventReader<TroopDamaged>,
+    troops: Query<(&Troop, Option<&DisabledExports>), Without<Quarantined>>,
+) {
+    for event in damage_events.iter() {
+        if let Ok((troop, disabled)) = troops.get(event.target) {
+            if !hook_enabled(troop, disabled, "on_damaged") {
+                continue;
+            }
+            if let Err(err) = with_scope(ScriptCategory::Battle, &[event.target], || {
+                script_env.call_if_instantiated_3::<f64, f64, i32, ()>(
+                    troop.get_wasm_script_handle(),
+                    "on_damaged",
+                    EntityId::from_entity(event.target),
+                    EntityId::from_entity(event.attacker),
+                    event.amount,
+                )
+            }) {
+                disable_export(
+                    &mut commands,
+                    event.target,
+                    troop.get_wasm_script_handle(),
+                    "on_damaged",
+                    err,
+                );
+            }
+        }
+    }
+}
+
+// The victim has already been despawned, so only its id is left to the killer.
+fn troop_kill_hook_system(
+    mut commands: Commands,
+    mut script_env: WasmScriptComponentEnv<Troop, ()>,
+    mut kill_events: EventReader<TroopKilled>,
+    troops: Query<(&Troop, Option<&DisabledExports>), Without<Quarantined>>,
+) {
+    for event in kill_events.iter() {
+        if let Ok((troop, disabled)) = troops.get(event.killer) {
+            if !hook_enabled(troop, disabled, "on_kill") {
+                continue;
+            }
+            if let Err(err) = with_scope(ScriptCategory::Battle, &[event.killer], || {
+                script_env.call_if_instantiated_2::<f64, f64, ()>(
+                    troop.get_wasm_script_handle(),
+                    "on_kill",
+                    EntityId::from_entity(event.killer),
+                    EntityId::from_entity(event.victim),
+                )
+            }) {
+                disable_export(
+                    &mut commands,
+                    event.killer,
+                    troop.get_wasm_script_handle(),
+                    "on_kill",
+                    err,
+                );
+            }
+        }
+    }
+}
+
+fn troop_wave_start_hook_system(
+    mut commands: Commands,
+    mut script_env: WasmScriptComponentEnv<Troop, ()>,
+    mut wave_events: EventReader<WaveStarted>,
+    troops: Query<(Entity, &Troop, Option<&DisabledExports>), Without<Quarantined>>,
+) {
+    for event in wave_events.iter() {
+        for (entity, troop, disabled) in troops.iter() {
+            if !hook_enabled(troop, disabled, "on_wave_start") {
+                continue;
+            }
+            if let Err(err) = with_scope(ScriptCategory::Battle, &[entity], || {
+                script_env.call_if_instantiated_2::<f64, i32, ()>(
+                    troop.get_wasm_script_handle(),
+                    "on_wave_start",
+                    EntityId::from_entity(entity),
+                    event.wave_id,
+                )
+            }) {
+                disable_export(
+                    &mut commands,
+                    entity,
+                    troop.get_wasm_script_handle(),
+                    "on_wave_start",
+                    err,
+                );
             }
         }
     }
@@ -438,10 +828,12 @@ fn troop_death_system(
 fn troop_staging_system(
     mut staging_locations: Query<(&GlobalTransform, &Faction, &mut StagingLocation)>,
     mut commands: Commands,
+    mut spawn_events: EventWriter<TroopSpawned>,
     delivery_scripts: Res<DeliveryScripts>,
     fonts: Res<FontAssets>,
     textures: Res<TextureAssets>,
     troop_types: Res<TroopTypes>,
+    mut rng: ResMut<GameRng>,
 ) {
     for (transform, faction, mut staging_location) in staging_locations.iter_mut() {
         staging_location
@@ -450,11 +842,8 @@ fn troop_staging_system(
             .for_each(|(troop_type, buffs)| {
                 if let Some(troop_type) = troop_types.get(troop_type) {
                     let position = Vec2::new(transform.translation().x, transform.translation().y)
-                        + Vec2::new(
-                            32. - rand::random::<f32>() * 64.,
-                            32. - rand::random::<f32>() * 64.,
-                        );
-                    spawn_troop(
+                        + Vec2::new(32. - rng.random() * 64., 32. - rng.random() * 64.);
+                    let troop = spawn_troop(
                         &mut commands,
                         position,
                         fonts.fira_sans.clone(),
@@ -468,6 +857,7 @@ fn troop_staging_system(
                         *faction,
                         buffs,
                     );
+                    spawn_events.send(TroopSpawned { troop });
                 }
             });
     }
@@ -1,17 +1,20 @@
 use bevy::prelude::*;
 use bevy_wasm_scripting::*;
-use rand::random;
 use wasmer::*;
 
 use crate::attacks::spawn_attack;
+use crate::attacks::Attack;
 use crate::attacks::AttackType;
 use crate::attacks::AttackTypes;
 use crate::battle::*;
 use crate::common_scripting::*;
 use crate::loading::TextureAssets;
+use crate::script_scope::check_entity;
+use crate::structure::Structure;
+use crate::world_random;
 
 type BattleScriptComponents = (&'static Faction, &'static Children);
-type BattleScriptResources = Res<'static, AttackTypes>;
+type BattleScriptResources = ResMut<'static, AttackTypes>;
 
 impl WasmScriptComponent for Troop {
     type ImportQueriedComponents = BattleScriptComponents;
@@ -33,7 +36,7 @@ fn get_battle_imports_from_world<S: 'static + Send + Sync>(
     world: &bevy_wasm_scripting::WorldPointer,
 ) -> Imports {
     let env = FunctionEnv::new(&mut wasmer_store.0, world.clone());
-    imports! {
+    let mut imports = imports! {
         "env" => {
             "despawn_entity" => Function::new_typed_with_env(&mut wasmer_store.0, &env, despawn_entity::<S>),
             "attach_child" => Function::new_typed_with_env(&mut wasmer_store.0, &env, attach_child::<S>),
@@ -42,43 +45,81 @@ fn get_battle_imports_from_world<S: 'static + Send + Sync>(
             "get_random" => Function::new_typed_with_env(&mut wasmer_store.0, &env, get_random),
             "get_script_value" => Function::new_typed_with_env(&mut wasmer_store.0, &env, get_script_value),
             "set_script_value" => Function::new_typed_with_env(&mut wasmer_store.0, &env, set_script_value),
+            "get_memory_i64" => Function::new_typed_with_env(&mut wasmer_store.0, &env, get_memory_i64),
+            "set_memory_i64" => Function::new_typed_with_env(&mut wasmer_store.0, &env, set_memory_i64),
+            "get_memory_f64" => Function::new_typed_with_env(&mut wasmer_store.0, &env, get_memory_f64),
+            "set_memory_f64" => Function::new_typed_with_env(&mut wasmer_store.0, &env, set_memory_f64),
             "heal_troop" => Function::new_typed_with_env(&mut wasmer_store.0, &env, heal_troop),
 
             "scan_enemies" => Function::new_typed_with_env(&mut wasmer_store.0, &env, scan_enemies),
             "get_enemy_count" => Function::new_typed_with_env(&mut wasmer_store.0, &env, get_enemy_count),
             "get_enemy" => Function::new_typed_with_env(&mut wasmer_store.0, &env, get_enemy),
             "get_nearest_enemy" => Function::new_typed_with_env(&mut wasmer_store.0, &env, get_nearest_enemy),
+            "scan_allies" => Function::new_typed_with_env(&mut wasmer_store.0, &env, scan_allies),
+            "get_ally_count" => Function::new_typed_with_env(&mut wasmer_store.0, &env, get_ally_count),
+            "get_ally" => Function::new_typed_with_env(&mut wasmer_store.0, &env, get_ally),
+
+            "get_health" => Function::new_typed_with_env(&mut wasmer_store.0, &env, get_health),
+            "get_max_health" => Function::new_typed_with_env(&mut wasmer_store.0, &env, get_max_health),
+            "get_troop_type_id" => Function::new_typed_with_env(&mut wasmer_store.0, &env, get_troop_type_id),
+            "get_faction" => Function::new_typed_with_env(&mut wasmer_store.0, &env, get_faction),
+            "get_cooldown_remaining" => Function::new_typed_with_env(&mut wasmer_store.0, &env, get_cooldown_remaining),
+            "get_attackers_of" => Function::new_typed_with_env(&mut wasmer_store.0, &env, get_attackers_of),
+            "get_attacker_of" => Function::new_typed_with_env(&mut wasmer_store.0, &env, get_attacker_of),
 
             "get_x_of" => Function::new_typed_with_env(&mut wasmer_store.0, &env, get_x_of),
             "get_y_of" => Function::new_typed_with_env(&mut wasmer_store.0, &env, get_y_of),
             "get_distance" => Function::new_typed_with_env(&mut wasmer_store.0, &env, get_distance),
+            "get_move_speed" => Function::new_typed_with_env(&mut wasmer_store.0, &env, get_move_speed),
+            "get_attack_range" => Function::new_typed_with_env(&mut wasmer_store.0, &env, get_attack_range),
 
             "retreat" => Function::new_typed_with_env(&mut wasmer_store.0, &env, retreat),
             "move_towards" => Function::new_typed_with_env(&mut wasmer_store.0, &env, move_towards),
             "attack_enemy" => Function::new_typed_with_env(&mut wasmer_store.0, &env, attack_enemy::<S>),
+
+            "scan_structures" => Function::new_typed_with_env(&mut wasmer_store.0, &env, scan_structures),
+            "get_structure_count" => Function::new_typed_with_env(&mut wasmer_store.0, &env, get_structure_count),
+            "get_structure" => Function::new_typed_with_env(&mut wasmer_store.0, &env, get_structure),
+            "get_nearest_structure" => Function::new_typed_with_env(&mut wasmer_store.0, &env, get_nearest_structure),
+            "get_structure_health" => Function::new_typed_with_env(&mut wasmer_store.0, &env, get_structure_health),
+            "attack_structure" => Function::new_typed_with_env(&mut wasmer_store.0, &env, attack_structure::<S>),
+
+            "define_attack" => Function::new_typed_with_env(&mut wasmer_store.0, &env, define_attack),
+            "get_attack_cooldown" => Function::new_typed_with_env(&mut wasmer_store.0, &env, get_attack_cooldown),
+            "set_attack_cooldown" => Function::new_typed_with_env(&mut wasmer_store.0, &env, set_attack_cooldown),
+            "get_attack_damage" => Function::new_typed_with_env(&mut wasmer_store.0, &env, get_attack_damage),
+            "set_attack_damage" => Function::new_typed_with_env(&mut wasmer_store.0, &env, set_attack_damage),
         }
-    }
+    };
+    add_guest_imports(&mut imports, wasmer_store, world);
+    imports
 }
 
-pub fn scan_enemies(env: FunctionEnvMut<WorldPointer>, me: EntityId) {
-    let scanned = if let Some(my_faction) = env.data().read().get::<Faction>(me.to_entity()) {
-        let world = env.data().write();
-        let mut query = world.query::<(Entity, &Troop, &Faction)>();
-        let mut seen_entities = Vec::new();
-        for (entity, _troop, faction) in query.iter(world) {
-            if entity == me.to_entity() {
-                continue;
-            } else if faction.faction_id != my_faction.faction_id {
-                seen_entities.push(entity);
-            }
-        }
-        seen_entities
+fn scan_troops(world: &mut World, me: Entity, allies: bool) -> Vec<Entity> {
+    let my_faction_id = if let Some(my_faction) = world.get::<Faction>(me) {
+        my_faction.faction_id
     } else {
-        Vec::new()
+        return Vec::new();
     };
+    let mut query = world.query::<(Entity, &Troop, &Faction)>();
+    let mut seen_entities = Vec::new();
+    for (entity, _troop, faction) in query.iter(world) {
+        if entity == me {
+            continue;
+        } else if (faction.faction_id == my_faction_id) == allies {
+            seen_entities.push(entity);
+        }
+    }
+    seen_entities
+}
+
+pub fn scan_enemies(env: FunctionEnvMut<WorldPointer>, me: EntityId) -> Result<(), RuntimeError> {
+    check_entity(env.data().read(), "scan_enemies", me.to_entity())?;
+    let scanned = scan_troops(env.data().write(), me.to_entity(), false);
     if let Some(mut troop) = env.data().write().get_mut::<Troop>(me.to_entity()) {
         troop.scan(scanned);
     }
+    Ok(())
 }
 pub fn get_enemy_count(env: FunctionEnvMut<WorldPointer>, me: EntityId) -> i32 {
     if let Some(troop) = env.data().read().get::<Troop>(me.to_entity()) {
@@ -99,77 +140,220 @@ pub fn get_enemy(env: FunctionEnvMut<WorldPointer>, me: EntityId, index: i32) ->
     }
 }
 
-pub fn get_nearest_enemy(env: FunctionEnvMut<WorldPointer>, me: EntityId) -> EntityId {
-    let my_transform = env.data().read().get::<GlobalTransform>(me.to_entity());
-    if my_transform.is_none() {
-        return EntityId::missing();
+pub fn scan_allies(env: FunctionEnvMut<WorldPointer>, me: EntityId) -> Result<(), RuntimeError> {
+    check_entity(env.data().read(), "scan_allies", me.to_entity())?;
+    let scanned = scan_troops(env.data().write(), me.to_entity(), true);
+    if let Some(mut troop) = env.data().write().get_mut::<Troop>(me.to_entity()) {
+        troop.scan_allies(scanned);
+    }
+    Ok(())
+}
+pub fn get_ally_count(env: FunctionEnvMut<WorldPointer>, me: EntityId) -> i32 {
+    if let Some(troop) = env.data().read().get::<Troop>(me.to_entity()) {
+        troop.seen_allies.len() as i32
+    } else {
+        0
+    }
+}
+pub fn get_ally(env: FunctionEnvMut<WorldPointer>, me: EntityId, index: i32) -> EntityId {
+    if let Some(troop) = env.data().read().get::<Troop>(me.to_entity()) {
+        troop
+            .seen_allies
+            .get(index as usize)
+            .map(|id| EntityId::from_entity(*id))
+            .unwrap_or(EntityId::missing())
+    } else {
+        EntityId::missing()
     }
-    let my_transform = my_transform.unwrap();
-    let my_location = Vec2::new(my_transform.translation().x, my_transform.translation().y);
+}
+
+pub fn get_health(env: FunctionEnvMut<WorldPointer>, troop: EntityId) -> i32 {
     env.data()
         .read()
-        .get::<Troop>(me.to_entity())
-        .and_then(|troop| {
-            troop.seen_troops.iter().min_by_key(|other| {
-                let other_transform = env.data().read().get::<GlobalTransform>(**other);
-                if other_transform.is_none() {
-                    return i32::MAX;
-                }
-                let other_transform = other_transform.unwrap();
-                let other_location = Vec2::new(
-                    other_transform.translation().x,
-                    other_transform.translation().y,
-                );
-                let distance = my_location.distance_squared(other_location) as i32;
-                distance
-            })
+        .get::<Troop>(troop.to_entity())
+        .map(|troop| troop.health)
+        .unwrap_or(0)
+}
+pub fn get_max_health(env: FunctionEnvMut<WorldPointer>, troop: EntityId) -> i32 {
+    env.data()
+        .read()
+        .get::<Troop>(troop.to_entity())
+        .map(|troop| troop.troop_type.health)
+        .unwrap_or(0)
+}
+pub fn get_troop_type_id(env: FunctionEnvMut<WorldPointer>, troop: EntityId) -> i32 {
+    env.data()
+        .read()
+        .get::<Troop>(troop.to_entity())
+        .map(|troop| troop.troop_type.id)
+        .unwrap_or(-1)
+}
+pub fn get_faction(env: FunctionEnvMut<WorldPointer>, entity: EntityId) -> i32 {
+    env.data()
+        .read()
+        .get::<Faction>(entity.to_entity())
+        .map(|faction| faction.faction_id)
+        .unwrap_or(-1)
+}
+pub fn get_cooldown_remaining(env: FunctionEnvMut<WorldPointer>, troop: EntityId) -> f32 {
+    env.data()
+        .read()
+        .get::<TroopCooldown>(troop.to_entity())
+        .map(|cooldown| cooldown.0.max(0.))
+        .unwrap_or(0.)
+}
+
+// Everyone with an attack still on its way to the target, each counted once.
+fn attackers_of(world: &mut World, target: Entity) -> Vec<Entity> {
+    let mut query = world.query::<&Attack>();
+    let mut attackers = Vec::new();
+    for attack in query.iter(world) {
+        if attack.target() == target && !attackers.contains(&attack.attacker()) {
+            attackers.push(attack.attacker());
+        }
+    }
+    attackers
+}
+pub fn get_attackers_of(env: FunctionEnvMut<WorldPointer>, me: EntityId) -> i32 {
+    attackers_of(env.data().write(), me.to_entity()).len() as i32
+}
+pub fn get_attacker_of(env: FunctionEnvMut<WorldPointer>, me: EntityId, index: i32) -> EntityId {
+    attackers_of(env.data().write(), me.to_entity())
+        .get(index as usize)
+        .map(|attacker| EntityId::from_entity(*attacker))
+        .unwrap_or(EntityId::missing())
+}
+
+// Troops are unparented and simulated on Transform, but structures can be children of other
+// buildings, so those need their GlobalTransform.
+pub fn get_position(world: &World, entity: Entity) -> Option<Vec3> {
+    if world.get::<Parent>(entity).is_some() {
+        world
+            .get::<GlobalTransform>(entity)
+            .map(|transform| transform.translation())
+    } else {
+        world
+            .get::<Transform>(entity)
+            .map(|transform| transform.translation)
+    }
+}
+
+fn get_nearest(world: &World, me: Entity, seen: &[Entity]) -> EntityId {
+    let my_location = if let Some(my_location) = get_position(world, me) {
+        Vec2::new(my_location.x, my_location.y)
+    } else {
+        return EntityId::missing();
+    };
+    seen.iter()
+        .filter(|entity| world.get_entity(**entity).is_some())
+        .min_by_key(|other| {
+            if let Some(other_location) = get_position(world, **other) {
+                let other_location = Vec2::new(other_location.x, other_location.y);
+                my_location.distance_squared(other_location) as i32
+            } else {
+                i32::MAX
+            }
         })
-        .filter(|entity| env.data().read().get_entity(**entity).is_some())
         .map(|entity| EntityId::from_entity(*entity))
         .unwrap_or(EntityId::missing())
 }
 
+pub fn get_nearest_enemy(env: FunctionEnvMut<WorldPointer>, me: EntityId) -> EntityId {
+    let world = env.data().read();
+    world
+        .get::<Troop>(me.to_entity())
+        .map(|troop| get_nearest(world, me.to_entity(), &troop.seen_troops))
+        .unwrap_or(EntityId::missing())
+}
+
 pub fn get_x_of(env: FunctionEnvMut<WorldPointer>, me: EntityId) -> f32 {
-    env.data()
-        .read()
-        .get::<Transform>(me.to_entity())
-        .map(|transform| transform.translation.x)
+    get_position(env.data().read(), me.to_entity())
+        .map(|position| position.x)
         .unwrap_or_else(|| {
             warn!("Could not find transform for {:?}.", me.to_entity());
             0.
         })
 }
 pub fn get_y_of(env: FunctionEnvMut<WorldPointer>, me: EntityId) -> f32 {
-    env.data()
-        .read()
-        .get::<Transform>(me.to_entity())
-        .map(|transform| transform.translation.y)
+    get_position(env.data().read(), me.to_entity())
+        .map(|position| position.y)
         .unwrap_or_else(|| {
             warn!("Could not find transform for {:?}.", me.to_entity());
             0.
         })
 }
 pub fn get_distance(env: FunctionEnvMut<WorldPointer>, me: EntityId, other: EntityId) -> f32 {
-    if let (Some(transform_a), Some(transform_b)) = (
-        env.data().read().get::<Transform>(me.to_entity()),
-        env.data().read().get::<Transform>(other.to_entity()),
+    let world = env.data().read();
+    if let (Some(position_a), Some(position_b)) = (
+        get_position(world, me.to_entity()),
+        get_position(world, other.to_entity()),
     ) {
-        transform_a.translation.distance(transform_b.translation)
+        position_a.distance(position_b)
     } else {
         -1.
     }
 }
 
-pub fn retreat(env: FunctionEnvMut<WorldPointer>, me: EntityId, speed: f32) {
+// Speed buffs multiply, while range buffs add a fraction of the base range.
+pub fn effective_move_speed(world: &World, entity: Entity) -> f32 {
+    world
+        .get::<Troop>(entity)
+        .map(|troop| troop.troop_type.move_speed * script_value(world, entity, SPEED_MOD_ID, 1.))
+        .unwrap_or(0.)
+}
+
+pub fn effective_attack_range(world: &World, entity: Entity) -> f32 {
+    world
+        .get::<Troop>(entity)
+        .map(|troop| {
+            troop.troop_type.attack_range
+                * (1. + script_value(world, entity, ATTACK_RANGE_MOD_ID, 0.))
+        })
+        .unwrap_or(0.)
+}
+
+fn in_attack_range(world: &World, me: Entity, target: Entity) -> bool {
+    if let (Some(position_a), Some(position_b)) =
+        (get_position(world, me), get_position(world, target))
+    {
+        position_a.distance(position_b) <= effective_attack_range(world, me)
+    } else {
+        false
+    }
+}
+
+pub fn get_move_speed(env: FunctionEnvMut<WorldPointer>, me: EntityId) -> f32 {
+    effective_move_speed(env.data().read(), me.to_entity())
+}
+
+pub fn get_attack_range(env: FunctionEnvMut<WorldPointer>, me: EntityId) -> f32 {
+    effective_attack_range(env.data().read(), me.to_entity())
+}
+
+pub fn retreat(
+    env: FunctionEnvMut<WorldPointer>,
+    me: EntityId,
+    speed: f32,
+) -> Result<(), RuntimeError> {
+    check_entity(env.data().read(), "retreat", me.to_entity())?;
     if let Some(mut troop) = env.data().write().get_mut::<Troop>(me.to_entity()) {
         troop.target = Some((troop.staging_point.clone(), speed));
     }
+    Ok(())
 }
 
-pub fn move_towards(env: FunctionEnvMut<WorldPointer>, me: EntityId, x: f32, y: f32, speed: f32) {
+pub fn move_towards(
+    env: FunctionEnvMut<WorldPointer>,
+    me: EntityId,
+    x: f32,
+    y: f32,
+    speed: f32,
+) -> Result<(), RuntimeError> {
+    check_entity(env.data().read(), "move_towards", me.to_entity())?;
     if let Some(mut troop) = env.data().write().get_mut::<Troop>(me.to_entity()) {
         troop.target = Some((Vec2::new(x, y), speed));
     }
+    Ok(())
 }
 
 pub fn attack_enemy<S: 'static + Send + Sync>(
@@ -177,7 +361,11 @@ pub fn attack_enemy<S: 'static + Send + Sync>(
     me: EntityId,
     enemy: EntityId,
     attack_id: i32,
-) -> f32 {
+) -> Result<f32, RuntimeError> {
+    check_entity(env.data().read(), "attack_enemy", me.to_entity())?;
+    if !in_attack_range(env.data().read(), me.to_entity(), enemy.to_entity()) {
+        return Ok(0.);
+    }
     let dodge_chance = env
         .data()
         .read()
@@ -185,11 +373,120 @@ pub fn attack_enemy<S: 'static + Send + Sync>(
         .and_then(|script_values| script_values.0.get(&DODGE_CHANCE_ID))
         .cloned()
         .unwrap_or(0.);
-    let attack_id = if random::<f32>() < dodge_chance {
-        -attack_id
+    let dodged = world_random(env.data().write()) < dodge_chance;
+    // A dodged attack plays out as its miss.
+    let played_id = if dodged { -attack_id } else { attack_id };
+    if let (Some(attack_type), Some(sprites)) = (
+        env.data()
+            .read()
+            .get_resource::<AttackTypes>()
+            .and_then(|attack_types| attack_types.get(played_id)),
+        env.data().read().get_resource::<TextureAssets>(),
+    ) {
+        let cooldown = attack_type.cooldown;
+        let world = env.data().write();
+        if let (Some(source), Some(target)) = (
+            CombatParticipant::of(world, me.to_entity()),
+            CombatParticipant::of(world, enemy.to_entity()),
+        ) {
+            send_combat_event(
+                world,
+                CombatEventKind::Attack { attack_id, dodged },
+                Some(source),
+                target,
+            );
+        }
+        spawn_attack(
+            &mut env.data().commands::<S>(),
+            me.to_entity(),
+            enemy.to_entity(),
+            sprites.attacks.clone(),
+            attack_type,
+        );
+        Ok(cooldown)
+    } else {
+        Ok(0.)
+    }
+}
+
+pub fn scan_structures(
+    env: FunctionEnvMut<WorldPointer>,
+    me: EntityId,
+) -> Result<(), RuntimeError> {
+    check_entity(env.data().read(), "scan_structures", me.to_entity())?;
+    let scanned = if let Some(my_faction) = env.data().read().get::<Faction>(me.to_entity()) {
+        let world = env.data().write();
+        let mut query = world.query::<(Entity, &Structure, &Faction)>();
+        let mut seen_entities = Vec::new();
+        for (entity, structure, faction) in query.iter(world) {
+            if faction.faction_id != my_faction.faction_id && !structure.is_destroyed() {
+                seen_entities.push(entity);
+            }
+        }
+        seen_entities
     } else {
-        attack_id
+        Vec::new()
     };
+    if let Some(mut troop) = env.data().write().get_mut::<Troop>(me.to_entity()) {
+        troop.scan_structures(scanned);
+    }
+    Ok(())
+}
+pub fn get_structure_count(env: FunctionEnvMut<WorldPointer>, me: EntityId) -> i32 {
+    if let Some(troop) = env.data().read().get::<Troop>(me.to_entity()) {
+        troop.seen_structures.len() as i32
+    } else {
+        0
+    }
+}
+pub fn get_structure(env: FunctionEnvMut<WorldPointer>, me: EntityId, index: i32) -> EntityId {
+    if let Some(troop) = env.data().read().get::<Troop>(me.to_entity()) {
+        troop
+            .seen_structures
+            .get(index as usize)
+            .map(|id| EntityId::from_entity(*id))
+            .unwrap_or(EntityId::missing())
+    } else {
+        EntityId::missing()
+    }
+}
+pub fn get_nearest_structure(env: FunctionEnvMut<WorldPointer>, me: EntityId) -> EntityId {
+    let world = env.data().read();
+    world
+        .get::<Troop>(me.to_entity())
+        .map(|troop| get_nearest(world, me.to_entity(), &troop.seen_structures))
+        .unwrap_or(EntityId::missing())
+}
+pub fn get_structure_health(env: FunctionEnvMut<WorldPointer>, structure: EntityId) -> i32 {
+    env.data()
+        .read()
+        .get::<Structure>(structure.to_entity())
+        .map(|structure| structure.health)
+        .unwrap_or(0)
+}
+
+pub fn attack_structure<S: 'static + Send + Sync>(
+    env: FunctionEnvMut<WorldPointer>,
+    me: EntityId,
+    structure: EntityId,
+    attack_id: i32,
+) -> Result<f32, RuntimeError> {
+    check_entity(env.data().read(), "attack_structure", me.to_entity())?;
+    let world = env.data().read();
+    // Only what `scan_structures` would have seen can be attacked.
+    let attackable = match (
+        world.get::<Structure>(structure.to_entity()),
+        world.get::<Faction>(structure.to_entity()),
+        world.get::<Faction>(me.to_entity()),
+    ) {
+        (Some(structure), Some(faction), Some(my_faction)) => {
+            faction.faction_id != my_faction.faction_id && !structure.is_destroyed()
+        }
+        _ => false,
+    };
+    if !attackable || !in_attack_range(env.data().read(), me.to_entity(), structure.to_entity()) {
+        return Ok(0.);
+    }
     if let (Some(attack_type), Some(sprites)) = (
         env.data()
             .read()
@@ -201,12 +498,91 @@ pub fn attack_enemy<S: 'static + Send + Sync>(
         spawn_attack(
             &mut env.data().commands::<S>(),
             me.to_entity(),
-            enemy.to_entity(),
+            structure.to_entity(),
             sprites.attacks.clone(),
             attack_type,
         );
-        cooldown
+        Ok(cooldown)
     } else {
-        0.
+        Ok(0.)
     }
 }
+
+// Scripts may add attacks built on the ones in files, like a faster enraged slash, and tune
+// them afterwards. Each belongs to the troop type that defined it, so one script can't retune
+// another's, and the attacks in files stay as they are for everyone.
+fn with_attack_types(
+    env: &FunctionEnvMut<WorldPointer>,
+    import: &'static str,
+    me: EntityId,
+    f: impl FnOnce(&mut AttackTypes, i32) -> bool,
+) -> Result<i8, RuntimeError> {
+    check_entity(env.data().read(), import, me.to_entity())?;
+    let world = env.data().write();
+    let owner = match world.get::<Troop>(me.to_entity()) {
+        Some(troop) => troop.troop_type.id,
+        None => return Ok(0),
+    };
+    Ok(world
+        .get_resource_mut::<AttackTypes>()
+        .map_or(false, |mut attack_types| f(&mut *attack_types, owner)) as i8)
+}
+
+pub fn define_attack(
+    env: FunctionEnvMut<WorldPointer>,
+    me: EntityId,
+    base_id: i32,
+    attack_id: i32,
+) -> Result<i8, RuntimeError> {
+    with_attack_types(&env, "define_attack", me, |attack_types, owner| {
+        attack_types.define(base_id, attack_id, owner)
+    })
+}
+
+pub fn get_attack_cooldown(env: FunctionEnvMut<WorldPointer>, attack_id: i32) -> f32 {
+    env.data()
+        .read()
+        .get_resource::<AttackTypes>()
+        .and_then(|attack_types| attack_types.0.get(&attack_id))
+        .map_or(-1., |attack_type| attack_type.cooldown)
+}
+
+/// Sets the cooldown of the attack and of its miss, since a dodged attack takes as long.
+pub fn set_attack_cooldown(
+    env: FunctionEnvMut<WorldPointer>,
+    me: EntityId,
+    attack_id: i32,
+    cooldown: f32,
+) -> Result<i8, RuntimeError> {
+    with_attack_types(&env, "set_attack_cooldown", me, |attack_types, owner| {
+        let cooldown = cooldown.max(0.);
+        let changed = attack_types.change_scripted(attack_id, owner, |attack_type| {
+            attack_type.cooldown = cooldown
+        });
+        attack_types.change_scripted(-attack_id, owner, |attack_type| {
+            attack_type.cooldown = cooldown
+        });
+        changed
+    })
+}
+
+pub fn get_attack_damage(env: FunctionEnvMut<WorldPointer>, attack_id: i32) -> i32 {
+    env.data()
+        .read()
+        .get_resource::<AttackTypes>()
+        .and_then(|attack_types| attack_types.0.get(&attack_id))
+        .map_or(-1, |attack_type| attack_type.damage())
+}
+
+pub fn set_attack_damage(
+    env: FunctionEnvMut<WorldPointer>,
+    me: EntityId,
+    attack_id: i32,
+    amount: i32,
+) -> Result<i8, RuntimeError> {
+    with_attack_types(&env, "set_attack_damage", me, |attack_types, owner| {
+        attack_types.change_scripted(attack_id, owner, |attack_type| {
+            attack_type.set_damage(amount)
+        })
+    })
+}
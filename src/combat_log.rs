@@ -0,0 +1,87 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+};
+
+use bevy::prelude::*;
+use serde::Serialize;
+
+use crate::{battle::CombatEvent, wave::CurrentWave, GameState};
+
+/// Set this environment variable to a file path to record every combat event as JSON lines.
+pub const COMBAT_LOG_ENV: &str = "HARVESTS_COMBAT_LOG";
+
+pub struct CombatLogPlugin;
+
+impl Plugin for CombatLogPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(CombatLog::from_env()).add_system_set(
+            SystemSet::on_update(GameState::Playing).with_system(combat_log_system),
+        );
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct CombatLog {
+    writer: Option<BufWriter<File>>,
+}
+
+impl CombatLog {
+    pub fn from_env() -> Self {
+        match std::env::var(COMBAT_LOG_ENV) {
+            Ok(path) => match File::create(&path) {
+                Ok(file) => {
+                    info!("Writing combat log to {}", path);
+                    Self {
+                        writer: Some(BufWriter::new(file)),
+                    }
+                }
+                Err(err) => {
+                    warn!("Could not create combat log {}: {}", path, err);
+                    Self::default()
+                }
+            },
+            Err(_) => Self::default(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct CombatLogLine<'a> {
+    wave_id: i32,
+    #[serde(flatten)]
+    event: &'a CombatEvent,
+}
+
+fn combat_log_system(
+    mut combat_log: ResMut<CombatLog>,
+    mut combat_events: EventReader<CombatEvent>,
+    current_wave: Res<CurrentWave>,
+) {
+    let writer = if let Some(writer) = combat_log.writer.as_mut() {
+        writer
+    } else {
+        return;
+    };
+    let mut written = false;
+    for event in combat_events.iter() {
+        let line = CombatLogLine {
+            wave_id: current_wave.wave.id,
+            event,
+        };
+        if let Err(err) = serde_json::to_writer(&mut *writer, &line)
+            .map_err(std::io::Error::from)
+            .and_then(|()| writer.write_all(b"\n"))
+        {
+            error!("Could not write combat log: {}", err);
+            combat_log.writer = None;
+            return;
+        }
+        written = true;
+    }
+    if written {
+        if let Err(err) = writer.flush() {
+            error!("Could not flush combat log: {}", err);
+        }
+    }
+}
@@ -1,31 +1,331 @@
-use bevy::{prelude::*, utils::HashMap};
+use std::{collections::BTreeMap, fmt::Display};
+
+use bevy::{
+    asset::{AssetLoader, LoadedAsset},
+    ecs::system::Command,
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
 use bevy_kira_audio::prelude::{Audio, *};
 use bevy_wasm_scripting::*;
+use serde::{Deserialize, Serialize};
 use wasmer::*;
 
 use crate::{
-    battle::Troop,
+    battle::{send_combat_event, CombatEventKind, CombatParticipant, Troop},
+    delivery::{DeliveryDropoff, DeliverySource},
+    director::Director,
     harvest::{Harvestable, HarvestableBundle, HarvestableTypes},
     loading::*,
+    script_errors::{script_name, ScriptError},
+    script_scope::{add_to_scope, check_entity, check_spawn, scope_owner},
+    stats::StatTypes,
+    world_random, SafeInsert,
 };
 
+// The stats the game reads itself. Their bounds and display are in `assets/stats`.
 pub const SPEED_MOD_ID: i32 = 0;
 pub const ATTACK_SPEED_MOD_ID: i32 = 1;
 pub const ATTACK_RANGE_MOD_ID: i32 = 2;
 pub const DODGE_CHANCE_ID: i32 = 3;
 
+/// How many slots of `ScriptMemory` a script may use, numbered from 0.
+pub const SCRIPT_MEMORY_SLOTS: i32 = 16;
+
+/// Scripts may be written as `.wat` text. They're compiled as they load, so everything past the
+/// loaders only sees wasm.
+pub fn compile_script(path: &str, bytes: Vec<u8>) -> anyhow::Result<Vec<u8>> {
+    if path.ends_with(".wat") {
+        Ok(wat2wasm(&bytes)?.into_owned())
+    } else {
+        Ok(bytes)
+    }
+}
+
+#[derive(Default)]
+pub struct WatScriptLoader;
+
+impl AssetLoader for WatScriptLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut bevy::asset::LoadContext,
+    ) -> bevy::utils::BoxedFuture<'a, anyhow::Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let name = load_context.path().display().to_string();
+            let wasm_bytes = wat2wasm(bytes)?.into_owned();
+            load_context.set_default_asset(LoadedAsset::new(WasmScript::Loaded(name, wasm_bytes)));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["wat"]
+    }
+}
+
 #[derive(Component, Default)]
 pub struct ScriptValues(pub HashMap<i32, f32>);
 
-pub fn get_random(_env: FunctionEnvMut<WorldPointer>) -> f32 {
-    rand::random::<f32>()
+/// State a script keeps on its entity between calls, like a target or the phase of a plan.
+/// Each slot holds 64 bits, written and read back as an i64 or an f64, so entity ids fit.
+/// Serializes as `SavedScriptMemory`, whose `version` says how to read the slots.
+#[derive(Component, Clone, Default, Serialize, Deserialize)]
+#[serde(into = "SavedScriptMemory", try_from = "SavedScriptMemory")]
+pub struct ScriptMemory(pub HashMap<i32, u64>);
+
+/// Bumped whenever the meaning of saved slots changes, so old saves are refused, not misread.
+pub const SCRIPT_MEMORY_VERSION: u32 = 1;
+
+/// The saved form of `ScriptMemory`. Version 1 keeps each used slot, in `0..SCRIPT_MEMORY_SLOTS`,
+/// with its raw 64 bits: an i64 as its two's complement, an f64 as `f64::to_bits`.
+#[derive(Serialize, Deserialize)]
+pub struct SavedScriptMemory {
+    pub version: u32,
+    pub slots: BTreeMap<i32, u64>,
+}
+
+impl From<ScriptMemory> for SavedScriptMemory {
+    fn from(memory: ScriptMemory) -> Self {
+        Self {
+            version: SCRIPT_MEMORY_VERSION,
+            slots: memory.0.into_iter().collect(),
+        }
+    }
+}
+
+impl TryFrom<SavedScriptMemory> for ScriptMemory {
+    type Error = String;
+
+    fn try_from(saved: SavedScriptMemory) -> Result<Self, Self::Error> {
+        if saved.version != SCRIPT_MEMORY_VERSION {
+            return Err(format!(
+                "script memory version {} can't be read, expected {}",
+                saved.version, SCRIPT_MEMORY_VERSION
+            ));
+        }
+        if let Some(slot) = saved
+            .slots
+            .keys()
+            .find(|slot| !(0..SCRIPT_MEMORY_SLOTS).contains(*slot))
+        {
+            return Err(format!(
+                "script memory slot {} is outside 0..{}",
+                slot, SCRIPT_MEMORY_SLOTS
+            ));
+        }
+        Ok(Self(saved.slots.into_iter().collect()))
+    }
+}
+
+/// Marks an entity whose main export, like `battle_action`, failed. Its script isn't called again,
+/// so a broken script reports once and leaves its entity idle instead of logging every step.
+/// Running out of fuel (see `script_fuel`) is a failure like any other trap.
+#[derive(Component)]
+pub struct Quarantined;
+
+pub fn quarantine(
+    commands: &mut Commands,
+    entity: Entity,
+    script: &Handle<WasmScript>,
+    export: &'static str,
+    err: impl Display,
+) {
+    commands.add(ScriptError::new(script, export, err));
+    commands.add(SafeInsert::new(entity, Quarantined));
+}
+
+/// Optional exports, like hooks or `king_ability`, that failed on an entity. Only the failing
+/// export stops being called, so a king whose ability traps keeps fighting.
+#[derive(Component, Default)]
+pub struct DisabledExports(pub HashSet<&'static str>);
+
+impl DisabledExports {
+    pub fn allows(disabled: Option<&Self>, export: &str) -> bool {
+        disabled.map_or(true, |disabled| !disabled.0.contains(export))
+    }
+}
+
+struct DisableExport {
+    entity: Entity,
+    export: &'static str,
+}
+
+impl Command for DisableExport {
+    fn write(self, world: &mut World) {
+        if let Some(mut entity) = world.get_entity_mut(self.entity) {
+            if let Some(mut disabled) = entity.get_mut::<DisabledExports>() {
+                disabled.0.insert(self.export);
+            } else {
+                let mut disabled = DisabledExports::default();
+                disabled.0.insert(self.export);
+                entity.insert(disabled);
+            }
+        }
+    }
+}
+
+pub fn disable_export(
+    commands: &mut Commands,
+    entity: Entity,
+    script: &Handle<WasmScript>,
+    export: &'static str,
+    err: impl Display,
+) {
+    commands.add(ScriptError::new(script, export, err));
+    commands.add(DisableExport { entity, export });
+}
+
+pub const LOG_INFO: i32 = 0;
+pub const LOG_WARN: i32 = 1;
+
+/// Pages of memory the game hands each script. Host functions can't see the instance calling
+/// them, so scripts import their memory from `env` instead of exporting it, and the imports that
+/// take text read it from there.
+pub const SCRIPT_MEMORY_PAGES: u32 = 32;
+
+pub struct GuestEnv {
+    world: WorldPointer,
+    memory: Memory,
+}
+
+/// Adds the script's memory and the imports that read text out of it.
+pub fn add_guest_imports(
+    imports: &mut Imports,
+    wasmer_store: &mut WasmerStore,
+    world: &WorldPointer,
+) {
+    let memory = Memory::new(
+        &mut wasmer_store.0,
+        MemoryType::new(SCRIPT_MEMORY_PAGES, None, false),
+    )
+    .expect("Could not allocate script memory");
+    let env = FunctionEnv::new(
+        &mut wasmer_store.0,
+        GuestEnv {
+            world: world.clone(),
+            memory: memory.clone(),
+        },
+    );
+    imports.define("env", "memory", memory);
+    imports.define(
+        "env",
+        "log",
+        Function::new_typed_with_env(&mut wasmer_store.0, &env, log),
+    );
+    imports.define(
+        "env",
+        "get_stat_id",
+        Function::new_typed_with_env(&mut wasmer_store.0, &env, get_stat_id),
+    );
+}
+
+fn read_text(
+    env: &FunctionEnvMut<GuestEnv>,
+    import: &'static str,
+    ptr: i32,
+    len: i32,
+) -> Result<String, RuntimeError> {
+    let view = env.data().memory.view(env);
+    let (start, len) = (ptr as u32 as u64, len as u32 as u64);
+    if start + len > view.data_size() {
+        return Err(RuntimeError::new(format!(
+            "{} text at {}..{} is outside the script's memory",
+            import,
+            start,
+            start + len
+        )));
+    }
+    let mut bytes = vec![0; len as usize];
+    view.read(start, &mut bytes)
+        .map_err(|err| RuntimeError::new(format!("{} could not read its text: {}", import, err)))?;
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+// Messages are prefixed with the script and the entity whose export is running.
+fn log_prefix(world: &World) -> String {
+    let owner = if let Some(owner) = scope_owner() {
+        owner
+    } else {
+        return "script".to_string();
+    };
+    let script = world
+        .get::<Troop>(owner)
+        .and_then(|troop| troop.troop_type.script.as_ref())
+        .or_else(|| {
+            world
+                .get::<DeliverySource>(owner)
+                .map(|source| &source.script)
+        })
+        .or_else(|| {
+            world
+                .get::<DeliveryDropoff>(owner)
+                .map(|dropoff| &dropoff.script)
+        })
+        .or_else(|| {
+            world
+                .get::<Director>(owner)
+                .map(|director| &director.script)
+        });
+    match (
+        script,
+        world.get_resource::<Assets<WasmScript>>(),
+        world.get_resource::<AssetServer>(),
+    ) {
+        (Some(script), Some(scripts), Some(asset_server)) => {
+            format!("{} {:?}", script_name(script, scripts, asset_server), owner)
+        }
+        _ => format!("{:?}", owner),
+    }
+}
+
+/// `log(level, ptr, len)` writes the UTF-8 text at `ptr` to the game's log.
+pub fn log(
+    env: FunctionEnvMut<GuestEnv>,
+    level: i32,
+    ptr: i32,
+    len: i32,
+) -> Result<(), RuntimeError> {
+    let message = read_text(&env, "log", ptr, len)?;
+    let prefix = log_prefix(env.data().world.read());
+    if level >= LOG_WARN {
+        warn!("[{}] {}", prefix, message);
+    } else {
+        info!("[{}] {}", prefix, message);
+    }
+    Ok(())
+}
+
+/// The id of the stat whose key is the UTF-8 text at `ptr`, or -1 when there's no such stat.
+pub fn get_stat_id(env: FunctionEnvMut<GuestEnv>, ptr: i32, len: i32) -> Result<i32, RuntimeError> {
+    let key = read_text(&env, "get_stat_id", ptr, len)?;
+    Ok(env
+        .data()
+        .world
+        .read()
+        .get_resource::<StatTypes>()
+        .and_then(|stats| stats.by_key(&key))
+        .map_or(-1, |stat| stat.id))
+}
+
+pub fn get_random(env: FunctionEnvMut<WorldPointer>) -> f32 {
+    world_random(env.data().write())
 }
 
-pub fn heal_troop(env: FunctionEnvMut<WorldPointer>, entity_id: EntityId, amount: i32) {
-    env.data()
-        .write()
+pub fn heal_troop(
+    env: FunctionEnvMut<WorldPointer>,
+    entity_id: EntityId,
+    amount: i32,
+) -> Result<(), RuntimeError> {
+    let world = env.data().write();
+    check_entity(world, "heal_troop", entity_id.to_entity())?;
+    world
         .get_mut::<Troop>(entity_id.to_entity())
         .map(|mut troop| troop.health = (troop.health + amount).clamp(0, troop.troop_type.health));
+    if let Some(target) = CombatParticipant::of(world, entity_id.to_entity()) {
+        send_combat_event(world, CombatEventKind::Heal { amount }, None, target);
+    }
+    Ok(())
 }
 
 pub fn set_script_value(
@@ -33,11 +333,30 @@ pub fn set_script_value(
     entity_id: EntityId,
     script_value_id: i32,
     new_value: f32,
-) {
-    env.data()
-        .write()
+) -> Result<(), RuntimeError> {
+    let world = env.data().write();
+    check_entity(world, "set_script_value", entity_id.to_entity())?;
+    let stats = world.get_resource::<StatTypes>();
+    let is_stat = stats.map_or(false, |stats| stats.get(script_value_id).is_some());
+    let new_value = stats.map_or(new_value, |stats| stats.clamp(script_value_id, new_value));
+    world
         .get_mut::<ScriptValues>(entity_id.to_entity())
         .and_then(|mut values| values.0.insert(script_value_id, new_value));
+    // Values that aren't stats are a script's own state, like the warrior's enrage, not buffs.
+    if is_stat {
+        if let Some(target) = CombatParticipant::of(world, entity_id.to_entity()) {
+            send_combat_event(
+                world,
+                CombatEventKind::Buff {
+                    script_value_id,
+                    value: new_value,
+                },
+                None,
+                target,
+            );
+        }
+    }
+    Ok(())
 }
 
 pub fn get_script_value(
@@ -46,73 +365,276 @@ pub fn get_script_value(
     script_value_id: i32,
     default: f32,
 ) -> f32 {
-    env.data()
-        .read()
-        .get::<ScriptValues>(entity_id.to_entity())
+    script_value(
+        env.data().read(),
+        entity_id.to_entity(),
+        script_value_id,
+        default,
+    )
+}
+
+pub fn script_value(world: &World, entity: Entity, script_value_id: i32, default: f32) -> f32 {
+    world
+        .get::<ScriptValues>(entity)
         .and_then(|values| values.0.get(&script_value_id))
         .cloned()
         .unwrap_or(default)
 }
 
+fn memory_slot(import: &'static str, slot: i32) -> Result<i32, RuntimeError> {
+    if (0..SCRIPT_MEMORY_SLOTS).contains(&slot) {
+        Ok(slot)
+    } else {
+        Err(RuntimeError::new(format!(
+            "{} slot {} is outside 0..{}",
+            import, slot, SCRIPT_MEMORY_SLOTS
+        )))
+    }
+}
+
+fn read_memory(
+    env: FunctionEnvMut<WorldPointer>,
+    import: &'static str,
+    entity_id: EntityId,
+    slot: i32,
+) -> Result<Option<u64>, RuntimeError> {
+    let slot = memory_slot(import, slot)?;
+    Ok(env
+        .data()
+        .read()
+        .get::<ScriptMemory>(entity_id.to_entity())
+        .and_then(|memory| memory.0.get(&slot))
+        .cloned())
+}
+
+fn write_memory(
+    env: FunctionEnvMut<WorldPointer>,
+    import: &'static str,
+    entity_id: EntityId,
+    slot: i32,
+    bits: u64,
+) -> Result<(), RuntimeError> {
+    let slot = memory_slot(import, slot)?;
+    let world = env.data().write();
+    check_entity(world, import, entity_id.to_entity())?;
+    if let Some(mut memory) = world.get_mut::<ScriptMemory>(entity_id.to_entity()) {
+        memory.0.insert(slot, bits);
+    }
+    Ok(())
+}
+
+pub fn get_memory_i64(
+    env: FunctionEnvMut<WorldPointer>,
+    entity_id: EntityId,
+    slot: i32,
+    default: i64,
+) -> Result<i64, RuntimeError> {
+    Ok(read_memory(env, "get_memory_i64", entity_id, slot)?.map_or(default, |bits| bits as i64))
+}
+
+pub fn set_memory_i64(
+    env: FunctionEnvMut<WorldPointer>,
+    entity_id: EntityId,
+    slot: i32,
+    value: i64,
+) -> Result<(), RuntimeError> {
+    write_memory(env, "set_memory_i64", entity_id, slot, value as u64)
+}
+
+pub fn get_memory_f64(
+    env: FunctionEnvMut<WorldPointer>,
+    entity_id: EntityId,
+    slot: i32,
+    default: f64,
+) -> Result<f64, RuntimeError> {
+    Ok(read_memory(env, "get_memory_f64", entity_id, slot)?.map_or(default, f64::from_bits))
+}
+
+pub fn set_memory_f64(
+    env: FunctionEnvMut<WorldPointer>,
+    entity_id: EntityId,
+    slot: i32,
+    value: f64,
+) -> Result<(), RuntimeError> {
+    write_memory(env, "set_memory_f64", entity_id, slot, value.to_bits())
+}
+
 pub fn play_sound(env: FunctionEnvMut<WorldPointer>, sound_id: i32) {
     let world = env.data().read();
-    let audio_assets = world.get_resource::<AudioAssets>().unwrap();
-    let audio = world.get_resource::<Audio>().unwrap();
-    audio.play(audio_assets.collection[sound_id as usize].clone());
+    // Headless worlds, like the script tests, run without audio.
+    if let (Some(audio_assets), Some(audio)) = (
+        world.get_resource::<AudioAssets>(),
+        world.get_resource::<Audio>(),
+    ) {
+        audio.play(audio_assets.collection[sound_id as usize].clone());
+    }
 }
 
 pub fn despawn_entity<S: 'static + Send + Sync>(
     env: FunctionEnvMut<WorldPointer>,
     entity_id: EntityId,
-) {
+) -> Result<(), RuntimeError> {
+    check_spawn("despawn_entity")?;
+    check_entity(env.data().read(), "despawn_entity", entity_id.to_entity())?;
     if let Some(mut entity) = env.data().commands::<S>().get_entity(entity_id.to_entity()) {
         entity.despawn();
     }
+    Ok(())
 }
 
 pub fn attach_child<S: 'static + Send + Sync>(
     env: FunctionEnvMut<WorldPointer>,
     me: EntityId,
     child: EntityId,
-) {
+) -> Result<(), RuntimeError> {
+    check_spawn("attach_child")?;
+    check_entity(env.data().read(), "attach_child", me.to_entity())?;
+    check_entity(env.data().read(), "attach_child", child.to_entity())?;
     if let Some(mut entity) = env.data().commands::<S>().get_entity(me.to_entity()) {
         entity.add_child(child.to_entity());
     }
+    Ok(())
 }
 
 pub fn spawn_harvestable_by_id<S: 'static + Send + Sync>(
     env: FunctionEnvMut<WorldPointer>,
     id: i32,
     real: i8,
-) -> EntityId {
+) -> Result<EntityId, RuntimeError> {
+    check_spawn("spawn_harvestable_by_id")?;
     let world = env.data().read();
     let sprite_assets = world.get_resource::<TextureAssets>().unwrap();
     let harvestables = world.get_resource::<HarvestableTypes>().unwrap();
     if let Some(harvestable) = harvestables.get(id) {
-        EntityId::from_entity({
-            env.data()
-                .commands::<S>()
-                .spawn(HarvestableBundle {
-                    sprite: SpriteSheetBundle {
-                        texture_atlas: sprite_assets.harvestables.clone(),
-                        sprite: TextureAtlasSprite {
-                            index: if real == 1 {
-                                harvestable.sprite_index
-                            } else {
-                                harvestable
-                                    .seed_sprite_index
-                                    .unwrap_or(harvestable.sprite_index)
-                            },
-                            ..Default::default()
+        let entity = env
+            .data()
+            .commands::<S>()
+            .spawn(HarvestableBundle {
+                sprite: SpriteSheetBundle {
+                    texture_atlas: sprite_assets.harvestables.clone(),
+                    sprite: TextureAtlasSprite {
+                        index: if real == 1 {
+                            harvestable.sprite_index
+                        } else {
+                            harvestable
+                                .seed_sprite_index
+                                .unwrap_or(harvestable.sprite_index)
                         },
-                        visibility: Visibility::INVISIBLE,
                         ..Default::default()
                     },
-                    harvestable: Harvestable(harvestable, real == 1),
-                })
-                .id()
-        })
+                    visibility: Visibility::INVISIBLE,
+                    ..Default::default()
+                },
+                harvestable: Harvestable(harvestable, real == 1),
+            })
+            .id();
+        add_to_scope(entity);
+        Ok(EntityId::from_entity(entity))
     } else {
-        EntityId::missing()
+        Ok(EntityId::missing())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::script_testing::ScriptTest;
+
+    #[test]
+    fn wat_scripts_compile_to_wasm() {
+        let wat = br#"(module
+            (func (export "battle_action") (param f64) (result f32)
+                f32.const 1))"#;
+        let wasm_bytes = compile_script("scripts/idle.wat", wat.to_vec()).unwrap();
+        assert!(wasm_bytes.starts_with(b"\0asm"));
+        assert_eq!(
+            compile_script("scripts/idle.wasm", wasm_bytes.clone()).unwrap(),
+            wasm_bytes
+        );
+    }
+
+    fn counting_script(slot: i32) -> String {
+        format!(
+            r#"(module
+                (import "env" "get_memory_i64" (func $get (param f64 i32 i64) (result i64)))
+                (import "env" "set_memory_i64" (func $set (param f64 i32 i64)))
+                (func (export "on_death") (param f64) (result i32) i32.const 1)
+                (func (export "battle_action") (param $me f64) (result f32)
+                    (local $count i64)
+                    (local.set $count
+                        (i64.add (call $get (local.get $me) (i32.const {slot}) (i64.const 0))
+                            (i64.const 1)))
+                    (call $set (local.get $me) (i32.const {slot}) (local.get $count))
+                    (f32.convert_i64_s (local.get $count))))"#,
+            slot = slot
+        )
+    }
+
+    #[test]
+    fn script_memory_survives_between_calls() {
+        let mut test = ScriptTest::new();
+        let troop = test.scripted_soldier(&counting_script(3));
+
+        assert_eq!(test.battle_action(troop), 1.);
+        assert_eq!(test.battle_action(troop), 2.);
+        let memory = test.world().get::<ScriptMemory>(troop).unwrap();
+        assert_eq!(memory.0.get(&3), Some(&2));
+    }
+
+    #[test]
+    fn script_memory_slots_are_bounded() {
+        let mut test = ScriptTest::new();
+        let troop = test.scripted_soldier(&counting_script(SCRIPT_MEMORY_SLOTS));
+
+        let err = test.try_battle_action(troop).unwrap_err();
+        assert!(err.contains("slot 16 is outside 0..16"), "{}", err);
+    }
+
+    #[test]
+    fn script_memory_saves_as_versioned_slots() {
+        let memory = ScriptMemory(HashMap::from_iter([
+            (0, (-7i64) as u64),
+            (3, 0.5f64.to_bits()),
+        ]));
+        let saved = ron::to_string(&memory).unwrap();
+        assert_eq!(
+            saved,
+            format!(
+                "(version:1,slots:{{0:{},3:{}}})",
+                (-7i64) as u64,
+                0.5f64.to_bits()
+            )
+        );
+
+        let loaded = ron::from_str::<ScriptMemory>(&saved).unwrap();
+        assert_eq!(loaded.0, memory.0);
+
+        assert!(ron::from_str::<ScriptMemory>("(version:2,slots:{})").is_err());
+        assert!(ron::from_str::<ScriptMemory>("(version:1,slots:{16:0})").is_err());
+    }
+
+    fn stat_lookup_script(ptr: i32, len: i32) -> String {
+        format!(
+            r#"(module
+                (import "env" "memory" (memory 1))
+                (import "env" "get_stat_id" (func $get_stat_id (param i32 i32) (result i32)))
+                (data (i32.const 16) "dodge_chance")
+                (func (export "on_death") (param f64) (result i32) i32.const 1)
+                (func (export "battle_action") (param $me f64) (result f32)
+                    (f32.convert_i32_s (call $get_stat_id (i32.const {ptr}) (i32.const {len})))))"#,
+            ptr = ptr,
+            len = len
+        )
+    }
+
+    #[test]
+    fn stat_ids_are_looked_up_from_script_memory() {
+        let mut test = ScriptTest::new();
+        let troop = test.scripted_soldier(&stat_lookup_script(16, 12));
+        assert_eq!(test.battle_action(troop), DODGE_CHANCE_ID as f32);
+
+        let troop = test.scripted_soldier(&stat_lookup_script(-16, 12));
+        let err = test.try_battle_action(troop).unwrap_err();
+        assert!(err.contains("outside the script's memory"), "{}", err);
     }
 }
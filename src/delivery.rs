@@ -1,13 +1,25 @@
-use bevy::prelude::*;
+use bevy::{
+    asset::{AssetLoader, LoadedAsset},
+    prelude::*,
+};
 use bevy_wasm_scripting::*;
 
-use crate::GameState;
+use crate::{
+    common_scripting::compile_script,
+    harvest::{CropHarvested, Harvestable, Harvested},
+    script_abi::{validate_script, ScriptRole},
+    script_errors::ScriptError,
+    script_profiler::{profiled, ScriptProfile},
+    script_scope::{with_scope, ScriptCategory},
+    GameState, SafeInsert,
+};
 
 pub struct DeliveryPlugin;
 
 impl Plugin for DeliveryPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<DeliveryItem>()
+            .init_asset_loader::<DeliveryScriptLoader>()
             .add_system_set(SystemSet::on_update(GameState::Playing).with_system(
                 ScriptSystemWithCommands::<_, DeliverySource>::wrap(IntoSystem::into_system(
                     delivery_sourcing_system,
@@ -26,6 +38,30 @@ impl Plugin for DeliveryPlugin {
     }
 }
 
+/// Loads `.delivery.wasm` and `.delivery.wat` scripts, checking them against the delivery ABI.
+#[derive(Default)]
+pub struct DeliveryScriptLoader;
+
+impl AssetLoader for DeliveryScriptLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut bevy::asset::LoadContext,
+    ) -> bevy::utils::BoxedFuture<'a, anyhow::Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let name = load_context.path().display().to_string();
+            let wasm_bytes = compile_script(&name, bytes.to_vec())?;
+            validate_script(&name, ScriptRole::Delivery, &wasm_bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(WasmScript::Loaded(name, wasm_bytes)));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["delivery.wasm", "delivery.wat"]
+    }
+}
+
 #[derive(Resource)]
 pub enum DeliveryItem {
     Nothing,
@@ -139,6 +175,8 @@ fn delivery_dragging_system(
 fn delivery_sourcing_system(
     mut mouse_location: Local<Vec2>,
     mut script_env: WasmScriptComponentEnv<DeliverySource, ()>,
+    mut script_errors: EventWriter<ScriptError>,
+    mut profile: Option<ResMut<ScriptProfile>>,
     delivery_anchors: Query<(Entity, &GlobalTransform, &DeliverySource, &DeliveryAnchor)>,
     camera: Query<(&Camera, &GlobalTransform)>,
     mouse_buttons: Res<Input<MouseButton>>,
@@ -157,11 +195,15 @@ fn delivery_sourcing_system(
             let closest_anchor = delivery_anchors
                 .iter()
                 .filter(|(entity, _delivery_transform, source, _anchor)| {
-                    match script_env.call_if_instantiated_1::<EntityId, i8>(
-                        &source.script,
-                        "can_produce",
-                        EntityId::from_entity(*entity),
-                    ) {
+                    match profiled(&mut profile, &source.script, "can_produce", || {
+                        with_scope(ScriptCategory::Delivery, &[*entity], || {
+                            script_env.call_if_instantiated_1::<EntityId, i8>(
+                                &source.script,
+                                "can_produce",
+                                EntityId::from_entity(*entity),
+                            )
+                        })
+                    }) {
                         Ok(can_produce) => {
                             if can_produce == 1 {
                                 true
@@ -170,7 +212,11 @@ fn delivery_sourcing_system(
                             }
                         }
                         Err(err) => {
-                            error!("Error in can_produce: {:?}", err);
+                            script_errors.send(ScriptError::new(
+                                &source.script,
+                                "can_produce",
+                                err,
+                            ));
                             false
                         }
                     }
@@ -183,11 +229,15 @@ fn delivery_sourcing_system(
                     get_anchor_distance_sq(mouse_world_location, delivery_transform, anchor);
                 if mouse_buttons.just_pressed(MouseButton::Left) {
                     if distance_to_anchor < anchor.distance_sq {
-                        match script_env.call_if_instantiated_1::<EntityId, EntityId>(
-                            &source.script,
-                            "produce",
-                            EntityId::from_entity(entity),
-                        ) {
+                        match profiled(&mut profile, &source.script, "produce", || {
+                            with_scope(ScriptCategory::Delivery, &[entity], || {
+                                script_env.call_if_instantiated_1::<EntityId, EntityId>(
+                                    &source.script,
+                                    "produce",
+                                    EntityId::from_entity(entity),
+                                )
+                            })
+                        }) {
                             Ok(produced_entity) => {
                                 if !produced_entity.is_missing() {
                                     *script_env.resources.0 = DeliveryItem::FromSource {
@@ -197,7 +247,11 @@ fn delivery_sourcing_system(
                                 }
                             }
                             Err(err) => {
-                                error!("Error in produce: {:?}", err);
+                                script_errors.send(ScriptError::new(
+                                    &source.script,
+                                    "produce",
+                                    err,
+                                ));
                             }
                         }
                     }
@@ -224,10 +278,15 @@ fn delivery_sourcing_system(
 }
 
 fn delivery_dropoff_system(
+    mut commands: Commands,
     mut mouse_location: Local<Vec2>,
     mut script_env: WasmScriptComponentEnv<DeliverySource, ()>,
+    mut script_errors: EventWriter<ScriptError>,
+    mut profile: Option<ResMut<ScriptProfile>>,
     delivery_source: Query<&DeliverySource>,
     delivery_anchors: Query<(Entity, &GlobalTransform, &DeliveryDropoff, &DeliveryAnchor)>,
+    harvestables: Query<(&Harvestable, Option<&Harvested>)>,
+    mut crops_harvested: EventWriter<CropHarvested>,
     camera: Query<(&Camera, &GlobalTransform)>,
     mouse_buttons: Res<Input<MouseButton>>,
     mut cursor_moved: EventReader<CursorMoved>,
@@ -248,12 +307,16 @@ fn delivery_dropoff_system(
                 let closest_anchor = delivery_anchors
                     .iter()
                     .filter(|(entity, _delivery_transform, dropoff, _anchor)| {
-                        match script_env.call_if_instantiated_2::<EntityId, EntityId, i8>(
-                            &dropoff.script,
-                            "can_receive",
-                            EntityId::from_entity(*entity),
-                            EntityId::from_entity(delivered),
-                        ) {
+                        match profiled(&mut profile, &dropoff.script, "can_receive", || {
+                            with_scope(ScriptCategory::Delivery, &[*entity, delivered], || {
+                                script_env.call_if_instantiated_2::<EntityId, EntityId, i8>(
+                                    &dropoff.script,
+                                    "can_receive",
+                                    EntityId::from_entity(*entity),
+                                    EntityId::from_entity(delivered),
+                                )
+                            })
+                        }) {
                             Ok(can_produce) => {
                                 if can_produce == 1 {
                                     true
@@ -262,7 +325,11 @@ fn delivery_dropoff_system(
                                 }
                             }
                             Err(err) => {
-                                error!("Error in can_receive: {:?}", err);
+                                script_errors.send(ScriptError::new(
+                                    &dropoff.script,
+                                    "can_receive",
+                                    err,
+                                ));
                                 false
                             }
                         }
@@ -274,33 +341,62 @@ fn delivery_dropoff_system(
                     let distance_to_anchor =
                         get_anchor_distance_sq(mouse_world_location, delivery_transform, anchor);
                     if distance_to_anchor < anchor.distance_sq {
-                        match script_env.call_if_instantiated_3::<EntityId, EntityId, EntityId, ()>(
-                            &dropoff.script,
-                            "receive",
-                            EntityId::from_entity(entity),
-                            EntityId::from_entity(delivered),
-                            EntityId::from_entity(source),
-                        ) {
+                        match profiled(&mut profile, &dropoff.script, "receive", || {
+                            with_scope(
+                                ScriptCategory::Delivery,
+                                &[entity, delivered, source],
+                                || {
+                                    script_env
+                                        .call_if_instantiated_3::<EntityId, EntityId, EntityId, ()>(
+                                            &dropoff.script,
+                                            "receive",
+                                            EntityId::from_entity(entity),
+                                            EntityId::from_entity(delivered),
+                                            EntityId::from_entity(source),
+                                        )
+                                },
+                            )
+                        }) {
                             Ok(()) => {
+                                if let Ok((harvestable, None)) = harvestables.get(delivered) {
+                                    if harvestable.0.plant && harvestable.1 {
+                                        crops_harvested.send(CropHarvested {
+                                            harvestable_id: harvestable.0.id,
+                                        });
+                                        commands.add(SafeInsert::new(delivered, Harvested));
+                                    }
+                                }
                                 *script_env.resources.0 = DeliveryItem::Nothing;
                             }
                             Err(err) => {
-                                error!("Error in receive: {:?}", err);
+                                script_errors.send(ScriptError::new(
+                                    &dropoff.script,
+                                    "receive",
+                                    err,
+                                ));
                             }
                         }
                         return;
                     }
                 }
                 if let Ok(delivery_source) = delivery_source.get(source) {
-                    match script_env.call_if_instantiated_2::<EntityId, EntityId, ()>(
-                        &delivery_source.script,
-                        "rejected",
-                        EntityId::from_entity(source),
-                        EntityId::from_entity(delivered),
-                    ) {
+                    match profiled(&mut profile, &delivery_source.script, "rejected", || {
+                        with_scope(ScriptCategory::Delivery, &[source, delivered], || {
+                            script_env.call_if_instantiated_2::<EntityId, EntityId, ()>(
+                                &delivery_source.script,
+                                "rejected",
+                                EntityId::from_entity(source),
+                                EntityId::from_entity(delivered),
+                            )
+                        })
+                    }) {
                         Ok(()) => {}
                         Err(err) => {
-                            error!("Error in rejected: {:?}", err);
+                            script_errors.send(ScriptError::new(
+                                &delivery_source.script,
+                                "rejected",
+                                err,
+                            ));
                         }
                     }
                 }
@@ -7,6 +7,9 @@ use crate::common_scripting::*;
 use crate::delivery::*;
 use crate::harvest::*;
 use crate::loading::*;
+use crate::script_scope::check_entity;
+use crate::structure::Structure;
+use crate::tower::TowerFoundation;
 
 // We don't need to include Commands or components referenced through Commands.
 type DeliveryScriptComponents = (
@@ -57,7 +60,7 @@ fn get_delivery_imports_from_world<S: 'static + Send + Sync>(
     world: &bevy_wasm_scripting::WorldPointer,
 ) -> Imports {
     let env = FunctionEnv::new(&mut wasmer_store.0, world.clone());
-    imports! {
+    let mut imports = imports! {
         "env" => {
             "despawn_entity" => Function::new_typed_with_env(&mut wasmer_store.0, &env, despawn_entity::<S>),
             "attach_child" => Function::new_typed_with_env(&mut wasmer_store.0, &env, attach_child::<S>),
@@ -66,6 +69,10 @@ fn get_delivery_imports_from_world<S: 'static + Send + Sync>(
             "get_random" => Function::new_typed_with_env(&mut wasmer_store.0, &env, get_random),
             "get_script_value" => Function::new_typed_with_env(&mut wasmer_store.0, &env, get_script_value),
             "set_script_value" => Function::new_typed_with_env(&mut wasmer_store.0, &env, set_script_value),
+            "get_memory_i64" => Function::new_typed_with_env(&mut wasmer_store.0, &env, get_memory_i64),
+            "set_memory_i64" => Function::new_typed_with_env(&mut wasmer_store.0, &env, set_memory_i64),
+            "get_memory_f64" => Function::new_typed_with_env(&mut wasmer_store.0, &env, get_memory_f64),
+            "set_memory_f64" => Function::new_typed_with_env(&mut wasmer_store.0, &env, set_memory_f64),
             "heal_troop" => Function::new_typed_with_env(&mut wasmer_store.0, &env, heal_troop),
 
             "get_harvestable_id" => Function::new_typed_with_env(&mut wasmer_store.0, &env, get_harvestable_id),
@@ -73,6 +80,7 @@ fn get_delivery_imports_from_world<S: 'static + Send + Sync>(
             "get_harvestable_is_plant" => Function::new_typed_with_env(&mut wasmer_store.0, &env, get_harvestable_is_plant),
             "get_harvestable_is_real" => Function::new_typed_with_env(&mut wasmer_store.0, &env, get_harvestable_is_real),
             "get_harvestable_troop_id" => Function::new_typed_with_env(&mut wasmer_store.0, &env, get_harvestable_troop_id),
+            "get_harvestable_tower_id" => Function::new_typed_with_env(&mut wasmer_store.0, &env, get_harvestable_tower_id),
 
             "get_harvest_spot_progress" => Function::new_typed_with_env(&mut wasmer_store.0, &env, get_harvest_spot_progress),
             "get_harvest_spot_progress_perc" => Function::new_typed_with_env(&mut wasmer_store.0, &env, get_harvest_spot_progress_perc),
@@ -87,8 +95,12 @@ fn get_delivery_imports_from_world<S: 'static + Send + Sync>(
             "get_free_child_harvest_spot" => Function::new_typed_with_env(&mut wasmer_store.0, &env, get_free_child_harvest_spot::<S>),
             "set_visibility" => Function::new_typed_with_env(&mut wasmer_store.0, &env, set_visibility::<S>),
             "stage_troop" => Function::new_typed_with_env(&mut wasmer_store.0, &env, stage_troop::<S>),
+            "can_build_tower" => Function::new_typed_with_env(&mut wasmer_store.0, &env, can_build_tower),
+            "build_tower" => Function::new_typed_with_env(&mut wasmer_store.0, &env, build_tower::<S>),
         }
-    }
+    };
+    add_guest_imports(&mut imports, wasmer_store, world);
+    imports
 }
 
 fn get_harvestable_id(env: FunctionEnvMut<WorldPointer>, entity_id: EntityId) -> i32 {
@@ -137,6 +149,14 @@ fn get_harvestable_troop_id(env: FunctionEnvMut<WorldPointer>, entity_id: Entity
         .unwrap_or(-1)
 }
 
+fn get_harvestable_tower_id(env: FunctionEnvMut<WorldPointer>, entity_id: EntityId) -> i32 {
+    env.data()
+        .read()
+        .get::<Harvestable>(entity_id.to_entity())
+        .and_then(|harvestable| harvestable.0.tower_id)
+        .unwrap_or(-1)
+}
+
 fn get_harvest_spot_progress(env: FunctionEnvMut<WorldPointer>, entity_id: EntityId) -> f32 {
     env.data()
         .read()
@@ -177,7 +197,12 @@ fn set_harvest_spot_progress<S: 'static + Send + Sync>(
     env: FunctionEnvMut<WorldPointer>,
     entity_id: EntityId,
     progress: f32,
-) {
+) -> Result<(), RuntimeError> {
+    check_entity(
+        env.data().read(),
+        "set_harvest_spot_progress",
+        entity_id.to_entity(),
+    )?;
     if let Some(mut harvest_spot) = env
         .data()
         .write()
@@ -191,13 +216,19 @@ fn set_harvest_spot_progress<S: 'static + Send + Sync>(
             }
         });
     }
+    Ok(())
 }
 
 fn set_harvest_spot_progress_perc<S: 'static + Send + Sync>(
     env: FunctionEnvMut<WorldPointer>,
     entity_id: EntityId,
     progress_perc: f32,
-) {
+) -> Result<(), RuntimeError> {
+    check_entity(
+        env.data().read(),
+        "set_harvest_spot_progress_perc",
+        entity_id.to_entity(),
+    )?;
     if let Some(mut harvest_spot) = env
         .data()
         .write()
@@ -211,13 +242,19 @@ fn set_harvest_spot_progress_perc<S: 'static + Send + Sync>(
             }
         });
     }
+    Ok(())
 }
 
 fn set_harvest_spot_harvest_time<S: 'static + Send + Sync>(
     env: FunctionEnvMut<WorldPointer>,
     entity_id: EntityId,
     harvest_time: f32,
-) {
+) -> Result<(), RuntimeError> {
+    check_entity(
+        env.data().read(),
+        "set_harvest_spot_harvest_time",
+        entity_id.to_entity(),
+    )?;
     if let Some(mut harvest_spot) = env
         .data()
         .write()
@@ -231,13 +268,19 @@ fn set_harvest_spot_harvest_time<S: 'static + Send + Sync>(
             }
         });
     }
+    Ok(())
 }
 
 fn set_harvest_spot_harvestable<S: 'static + Send + Sync>(
     env: FunctionEnvMut<WorldPointer>,
     entity_id: EntityId,
     harvestable: i32,
-) {
+) -> Result<(), RuntimeError> {
+    check_entity(
+        env.data().read(),
+        "set_harvest_spot_harvestable",
+        entity_id.to_entity(),
+    )?;
     let world = env.data().write();
     let harvestable = world
         .get_resource::<HarvestableTypes>()
@@ -252,6 +295,7 @@ fn set_harvest_spot_harvestable<S: 'static + Send + Sync>(
             }
         });
     }
+    Ok(())
 }
 
 fn get_free_child_harvest_spot<S: 'static + Send + Sync>(
@@ -261,7 +305,13 @@ fn get_free_child_harvest_spot<S: 'static + Send + Sync>(
     if let Some(children) = env.data().read().get::<Children>(entity_id.to_entity()) {
         for &child in children.iter() {
             if let Some(harvest_spot) = env.data().read().get::<HarvestSpot>(child) {
-                if harvest_spot.harvestable_type.is_none() {
+                let destroyed = env
+                    .data()
+                    .read()
+                    .get::<Structure>(child)
+                    .map(|structure| structure.is_destroyed())
+                    .unwrap_or(false);
+                if harvest_spot.harvestable_type.is_none() && !destroyed {
                     return EntityId::from_entity(child);
                 }
             }
@@ -274,7 +324,8 @@ fn set_visibility<S: 'static + Send + Sync>(
     env: FunctionEnvMut<WorldPointer>,
     entity_id: EntityId,
     new_visibility: i8,
-) {
+) -> Result<(), RuntimeError> {
+    check_entity(env.data().read(), "set_visibility", entity_id.to_entity())?;
     if let Some(mut visibility) = env
         .data()
         .write()
@@ -282,12 +333,14 @@ fn set_visibility<S: 'static + Send + Sync>(
     {
         visibility.is_visible = new_visibility == 1;
     }
+    Ok(())
 }
 fn stage_troop<S: 'static + Send + Sync>(
     env: FunctionEnvMut<WorldPointer>,
     entity_id: EntityId,
     troop_id: i32,
-) {
+) -> Result<(), RuntimeError> {
+    check_entity(env.data().read(), "stage_troop", entity_id.to_entity())?;
     if let Some(mut staging) = env
         .data()
         .write()
@@ -295,4 +348,39 @@ fn stage_troop<S: 'static + Send + Sync>(
     {
         staging.stage(troop_id);
     }
+    Ok(())
+}
+
+fn can_build_tower(env: FunctionEnvMut<WorldPointer>, entity_id: EntityId) -> i8 {
+    let world = env.data().read();
+    world
+        .get::<TowerFoundation>(entity_id.to_entity())
+        .map(|foundation| {
+            let standing = foundation
+                .tower
+                .map(|tower| world.get_entity(tower).is_some())
+                .unwrap_or(false);
+            if foundation.pending.is_none() && !standing {
+                1
+            } else {
+                0
+            }
+        })
+        .unwrap_or(0)
+}
+
+fn build_tower<S: 'static + Send + Sync>(
+    env: FunctionEnvMut<WorldPointer>,
+    entity_id: EntityId,
+    tower_id: i32,
+) -> Result<(), RuntimeError> {
+    check_entity(env.data().read(), "build_tower", entity_id.to_entity())?;
+    if let Some(mut foundation) = env
+        .data()
+        .write()
+        .get_mut::<TowerFoundation>(entity_id.to_entity())
+    {
+        foundation.build(tower_id);
+    }
+    Ok(())
 }
@@ -0,0 +1,161 @@
+use bevy::{prelude::*, utils::HashMap};
+use bevy_wasm_scripting::*;
+
+use crate::{
+    battle::{spawn_troop, Faction, TroopTypes},
+    common_scripting::{quarantine, Quarantined},
+    loading::*,
+    scenario::{ObjectiveProgress, Scenarios},
+    script_scope::{with_scope, ScriptCategory},
+    simulation::BattleStage,
+    wave::*,
+    GameRng, GameState,
+};
+
+pub struct DirectorPlugin;
+
+impl Plugin for DirectorPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_enter(GameState::Playing).with_system(spawn_director))
+            .add_system_set_to_stage(
+                BattleStage,
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(ScriptSystemWithCommands::<_, Director>::wrap(
+                        IntoSystem::into_system(director_tick_system),
+                    ))
+                    .with_system(director_spawning_system)
+                    .with_system(director_wave_system),
+            )
+            .add_wasm_script_component::<Director>();
+    }
+}
+
+// Spawn points, in the order scripts refer to them by index.
+pub const SPAWN_POINTS: [(f32, f32); 4] = [
+    (NORTH_X, NORTH_Y),
+    (EAST_X, EAST_Y),
+    (SOUTH_X, SOUTH_Y),
+    (WEST_X, WEST_Y),
+];
+
+/// Drives the waves of a scenario from its script. While a director is around, waves only end
+/// when it says so.
+#[derive(Component)]
+pub struct Director {
+    pub script: Handle<WasmScript>,
+    pub buffs: HashMap<i32, f32>,
+    pub staged: Vec<(usize, i32, HashMap<i32, f32>)>,
+    pub wave_ended: bool,
+}
+
+impl Director {
+    pub fn new(script: Handle<WasmScript>) -> Self {
+        Self {
+            script,
+            buffs: HashMap::default(),
+            staged: Vec::new(),
+            wave_ended: false,
+        }
+    }
+
+    /// Stages an enemy with the buffs set since the last one.
+    pub fn stage(&mut self, spawn_point: usize, troop_id: i32) {
+        let buffs = std::mem::take(&mut self.buffs);
+        self.staged.push((spawn_point, troop_id, buffs));
+    }
+}
+
+fn spawn_director(mut commands: Commands, scenarios: Res<Scenarios>) {
+    if let Some(script) = scenarios.selected().and_then(|scenario| scenario.director) {
+        commands.spawn(Director::new(script));
+    }
+}
+
+fn director_tick_system(
+    mut commands: Commands,
+    mut script_env: WasmScriptComponentEnv<Director, ()>,
+    directors: Query<(Entity, &Director), Without<Quarantined>>,
+    current_wave: Res<CurrentWave>,
+    progress: Res<ObjectiveProgress>,
+) {
+    if current_wave.wave.id < 0 || progress.outcome.is_some() {
+        return;
+    }
+    for (entity, director) in directors.iter() {
+        // The director's imports change nothing the scope guards, it only names the caller.
+        if let Err(err) = with_scope(ScriptCategory::Battle, &[entity], || {
+            script_env.call_if_instantiated_1::<f32, ()>(
+                director.get_wasm_script_handle(),
+                "director_tick",
+                current_wave.time_in_wave,
+            )
+        }) {
+            quarantine(
+                &mut commands,
+                entity,
+                director.get_wasm_script_handle(),
+                "director_tick",
+                err,
+            );
+        }
+    }
+}
+
+fn director_spawning_system(
+    mut commands: Commands,
+    mut directors: Query<&mut Director>,
+    delivery_scripts: Res<DeliveryScripts>,
+    fonts: Res<FontAssets>,
+    textures: Res<TextureAssets>,
+    troop_types: Res<TroopTypes>,
+    mut rng: ResMut<GameRng>,
+) {
+    for mut director in directors.iter_mut() {
+        for (spawn_point, troop_id, buffs) in director.staged.drain(..) {
+            if let (Some((x, y)), Some(troop_type)) =
+                (SPAWN_POINTS.get(spawn_point), troop_types.get(troop_id))
+            {
+                let position = Vec2::new(*x, *y)
+                    + Vec2::new(32. - rng.random() * 64., 32. - rng.random() * 64.);
+                spawn_troop(
+                    &mut commands,
+                    position,
+                    fonts.fira_sans.clone(),
+                    textures.troops.clone(),
+                    delivery_scripts.deliver_enemy.clone(),
+                    troop_type,
+                    Faction::enemy(),
+                    buffs,
+                );
+            } else {
+                warn!(
+                    "Director tried to spawn troop {} at spawn point {}",
+                    troop_id, spawn_point
+                );
+            }
+        }
+    }
+}
+
+fn director_wave_system(
+    mut directors: Query<&mut Director>,
+    mut current_wave: ResMut<CurrentWave>,
+    mut progress: ResMut<ObjectiveProgress>,
+    waves: Res<Waves>,
+) {
+    for mut director in directors.iter_mut() {
+        if !director.wave_ended {
+            continue;
+        }
+        director.wave_ended = false;
+        if current_wave.wave.id < 0 {
+            continue;
+        }
+        let next_wave = waves.get(current_wave.wave.id + 1);
+        if next_wave.id == -1 {
+            progress.all_waves_cleared = true;
+        } else {
+            current_wave.go_to_next_wave(next_wave);
+        }
+    }
+}
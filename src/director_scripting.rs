@@ -0,0 +1,100 @@
+use bevy::prelude::*;
+use bevy_wasm_scripting::*;
+use wasmer::*;
+
+use crate::battle::{Faction, Troop};
+use crate::common_scripting::*;
+use crate::director::{Director, SPAWN_POINTS};
+use crate::stats::StatTypes;
+use crate::wave::CurrentWave;
+
+type DirectorScriptComponents = (&'static Troop, &'static Faction);
+type DirectorScriptResources = Res<'static, CurrentWave>;
+
+impl WasmScriptComponent for Director {
+    type ImportQueriedComponents = DirectorScriptComponents;
+    type ImportResources = DirectorScriptResources;
+
+    fn get_imports_from_world(
+        wasmer_store: &mut bevy_wasm_scripting::WasmerStore,
+        world: &bevy_wasm_scripting::WorldPointer,
+    ) -> Imports {
+        let env = FunctionEnv::new(&mut wasmer_store.0, world.clone());
+        let mut imports = imports! {
+            "env" => {
+                "play_sound" => Function::new_typed_with_env(&mut wasmer_store.0, &env, play_sound),
+                "get_random" => Function::new_typed_with_env(&mut wasmer_store.0, &env, get_random),
+
+                "get_wave_id" => Function::new_typed_with_env(&mut wasmer_store.0, &env, get_wave_id),
+                "get_army_size" => Function::new_typed_with_env(&mut wasmer_store.0, &env, get_army_size),
+                "get_invader_count" => Function::new_typed_with_env(&mut wasmer_store.0, &env, get_invader_count),
+                "set_spawn_buff" => Function::new_typed_with_env(&mut wasmer_store.0, &env, set_spawn_buff),
+                "spawn_invader" => Function::new_typed_with_env(&mut wasmer_store.0, &env, spawn_invader),
+                "end_wave" => Function::new_typed_with_env(&mut wasmer_store.0, &env, end_wave),
+            }
+        };
+        add_guest_imports(&mut imports, wasmer_store, world);
+        imports
+    }
+
+    fn get_wasm_script_handle(&self) -> &Handle<WasmScript> {
+        &self.script
+    }
+}
+
+fn with_director(world: &mut World, f: impl FnOnce(&mut Director)) {
+    let mut query = world.query::<&mut Director>();
+    if let Some(mut director) = query.iter_mut(world).next() {
+        f(&mut director);
+    }
+}
+
+fn count_troops(world: &mut World, faction_id: i32) -> i32 {
+    let mut query = world.query_filtered::<&Faction, With<Troop>>();
+    query
+        .iter(world)
+        .filter(|faction| faction.faction_id == faction_id)
+        .count() as i32
+}
+
+fn get_wave_id(env: FunctionEnvMut<WorldPointer>) -> i32 {
+    env.data()
+        .read()
+        .get_resource::<CurrentWave>()
+        .map(|current_wave| current_wave.wave.id)
+        .unwrap_or(-1)
+}
+
+fn get_army_size(env: FunctionEnvMut<WorldPointer>) -> i32 {
+    count_troops(env.data().write(), Faction::player().faction_id)
+}
+
+fn get_invader_count(env: FunctionEnvMut<WorldPointer>) -> i32 {
+    count_troops(env.data().write(), Faction::enemy().faction_id)
+}
+
+fn set_spawn_buff(env: FunctionEnvMut<WorldPointer>, script_value_id: i32, value: f32) {
+    let world = env.data().write();
+    let value = world
+        .get_resource::<StatTypes>()
+        .map_or(value, |stats| stats.clamp(script_value_id, value));
+    with_director(world, |director| {
+        director.buffs.insert(script_value_id, value);
+    });
+}
+
+fn spawn_invader(env: FunctionEnvMut<WorldPointer>, spawn_point: i32, troop_id: i32) -> i8 {
+    if spawn_point < 0 || spawn_point as usize >= SPAWN_POINTS.len() {
+        return 0;
+    }
+    let mut staged = 0;
+    with_director(env.data().write(), |director| {
+        director.stage(spawn_point as usize, troop_id);
+        staged = 1;
+    });
+    staged
+}
+
+fn end_wave(env: FunctionEnvMut<WorldPointer>) {
+    with_director(env.data().write(), |director| director.wave_ended = true);
+}
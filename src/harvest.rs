@@ -8,10 +8,13 @@ use bevy::{
 use bevy_wasm_scripting::WasmScript;
 
 use crate::{
-    common_scripting::ScriptValues,
+    battle::Faction,
+    common_scripting::{ScriptMemory, ScriptValues},
     delivery::{DeliveryAnchor, DeliveryDropoff, DeliverySource},
     helper::HelperTextBundle,
     loading::{DeliveryScripts, FontAssets, HarvestableAssets, TextureAssets},
+    simulation::{BattleClock, BattleStage},
+    structure::Structure,
     GameState,
 };
 
@@ -21,10 +24,12 @@ impl Plugin for HarvestPlugin {
     fn build(&self, app: &mut App) {
         app.add_asset::<HarvestableType>()
             .init_asset_loader::<HarvestableAssetLoader>()
+            .add_event::<CropHarvested>()
             .add_system_set(
                 SystemSet::on_enter(GameState::Playing).with_system(spawn_harvest_spots),
             )
-            .add_system_set(
+            .add_system_set_to_stage(
+                BattleStage,
                 SystemSet::on_update(GameState::Playing).with_system(harvest_base_timer_system),
             )
             .add_system_set(
@@ -73,6 +78,7 @@ pub struct HarvestableType {
     pub value: i32,
     pub plant: bool,
     pub troop_id: Option<i32>,
+    pub tower_id: Option<i32>,
     pub name: String,
     pub description: String,
 }
@@ -120,6 +126,15 @@ impl HarvestableTypes {
     }
 }
 
+/// Sent the first time a ripe crop is delivered somewhere.
+pub struct CropHarvested {
+    pub harvestable_id: i32,
+}
+
+/// Put on a crop once it has counted as harvested, so moving it again doesn't count twice.
+#[derive(Component)]
+pub struct Harvested;
+
 #[derive(Component)]
 pub struct Harvestable(pub HarvestableType, pub bool);
 
@@ -134,9 +149,12 @@ pub struct HarvestSpotBundle {
     sprite: SpriteSheetBundle,
     harvest_spot: HarvestSpot,
     script_values: ScriptValues,
+    script_memory: ScriptMemory,
     delivery_source: DeliverySource,
     delivery_location: DeliveryDropoff,
     delivery_anchor: DeliveryAnchor,
+    structure: Structure,
+    faction: Faction,
 }
 
 pub fn spawn_harvest_spot<'a, 'b, 'c>(
@@ -175,6 +193,9 @@ pub fn spawn_harvest_spot<'a, 'b, 'c>(
             delivery_source: DeliverySource::new(script.clone()),
             delivery_location: DeliveryDropoff::new(script),
             script_values: Default::default(),
+            script_memory: Default::default(),
+            structure: Structure::new(10),
+            faction: Faction::player(),
         })
         .add_child(helper)
         .id()
@@ -202,11 +223,11 @@ fn spawn_harvest_spots(
 
 fn harvest_base_timer_system(
     mut query: Query<(&mut TextureAtlasSprite, &mut HarvestSpot)>,
-    time: Res<Time>,
+    clock: Res<BattleClock>,
 ) {
     for (mut sprite, mut spot) in query.iter_mut() {
         if spot.harvestable_type.is_some() {
-            spot.progress += time.delta_seconds();
+            spot.progress += clock.delta_seconds();
             let frame =
                 (((spot.progress / spot.harvest_time) * (HARVEST_FRAMES - 1) as f32).floor()
                     as usize)
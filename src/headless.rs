@@ -0,0 +1,261 @@
+//! A battle without a window, textures or audio. Definitions and scripts are read straight from
+//! `assets`, and the battle only advances when `step` is called, so balance sweeps and script tests
+//! run as fast as the simulation allows.
+
+use std::{fs, path::PathBuf};
+
+use anyhow::anyhow;
+use bevy::{
+    asset::{AssetPlugin, FileAssetIo},
+    ecs::system::CommandQueue,
+    hierarchy::HierarchyPlugin,
+    prelude::*,
+    transform::TransformPlugin,
+    utils::HashMap,
+};
+use bevy_wasm_scripting::*;
+use serde::de::DeserializeOwned;
+
+use crate::{
+    attacks::{AttackType, AttackTypes},
+    battle::{
+        spawn_troop, troop_systems, CombatEvent, Faction, StagingLocation, Troop, TroopDamaged,
+        TroopKilled, TroopSpawned, TroopType, TroopTypes,
+    },
+    common_scripting::compile_script,
+    delivery::{DeliveryDropoff, DeliveryItem, DeliverySource},
+    harvest::{HarvestableType, HarvestableTypes},
+    loading::{DeliveryScripts, FontAssets, TextureAssets},
+    script_abi::{validate_script, ScriptRole},
+    script_errors::ScriptError,
+    script_fuel::ScriptFuelPlugin,
+    simulation::BattleClock,
+    stats::{StatType, StatTypes},
+    structure::{structure_ruin_system, Structure},
+    wave::{wave_spawning_system, CurrentWave, InvasionSpots, Wave, WaveStarted, Waves},
+    GameRng, GameState,
+};
+
+fn asset_path(path: &str) -> PathBuf {
+    FileAssetIo::get_base_path().join("assets").join(path)
+}
+
+fn load_definitions<T: DeserializeOwned>(folder: &str, extension: &str) -> anyhow::Result<Vec<T>> {
+    let mut definitions = Vec::new();
+    for entry in fs::read_dir(asset_path(folder))? {
+        let path = entry?.path();
+        if path.extension().map_or(false, |ext| ext == extension) {
+            let bytes = fs::read(&path)?;
+            let definition = ron::de::from_bytes(&bytes)
+                .map_err(|err| anyhow!("Could not parse {}: {}", path.display(), err))?;
+            definitions.push(definition);
+        }
+    }
+    Ok(definitions)
+}
+
+pub struct HeadlessBattle {
+    pub app: App,
+    battle_stage: SystemStage,
+}
+
+impl HeadlessBattle {
+    /// Builds the battle on top of `app`, which can bring its own plugins, like logging.
+    pub fn new(mut app: App) -> anyhow::Result<Self> {
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(AssetPlugin::default())
+            .add_plugin(TransformPlugin)
+            .add_plugin(HierarchyPlugin)
+            .add_plugin(WasmPlugin)
+            .add_plugin(ScriptFuelPlugin)
+            .add_state(GameState::Playing)
+            .add_wasm_script_component::<Troop>()
+            .add_wasm_script_component::<DeliverySource>()
+            .add_wasm_script_component::<DeliveryDropoff>()
+            .add_event::<CombatEvent>()
+            .add_event::<ScriptError>()
+            .init_resource::<Events<TroopSpawned>>()
+            .init_resource::<Events<TroopDamaged>>()
+            .init_resource::<Events<TroopKilled>>()
+            .init_resource::<Events<WaveStarted>>()
+            .init_resource::<DeliveryItem>()
+            .init_resource::<GameRng>()
+            .init_resource::<BattleClock>()
+            .init_resource::<CurrentWave>()
+            .init_resource::<InvasionSpots>();
+        app.world.resource_mut::<GameRng>().reseed(0);
+
+        let mut battle = Self {
+            app,
+            battle_stage: SystemStage::parallel()
+                .with_system_set(troop_systems())
+                .with_system_set(
+                    SystemSet::on_update(GameState::Playing)
+                        .with_system(wave_spawning_system)
+                        .with_system(structure_ruin_system),
+                ),
+        };
+        let mut troop_types = HashMap::default();
+        for mut troop_type in load_definitions::<TroopType>("troops", "troop")? {
+            let name = format!("troop:{}", troop_type.id);
+            let (script, exports) =
+                battle.load_script(name, &troop_type.script_path, ScriptRole::Troop)?;
+            troop_type.exports = exports;
+            troop_type.script = Some(script);
+            troop_types.insert(troop_type.id, troop_type);
+        }
+        let attack_types = load_definitions::<AttackType>("attacks", "attack")?
+            .into_iter()
+            .map(|attack_type| (attack_type.id, attack_type))
+            .collect();
+        let harvestable_types = load_definitions::<HarvestableType>("harvestables", "harvest")?
+            .into_iter()
+            .map(|harvestable_type| (harvestable_type.id, harvestable_type))
+            .collect();
+        let stat_types = load_definitions::<StatType>("stats", "stat")?
+            .into_iter()
+            .map(|stat_type| (stat_type.id, stat_type))
+            .collect();
+        let waves = Waves::new(load_definitions::<Wave>("waves", "wave")?);
+        let mut delivery_script = |name: &str| -> anyhow::Result<Handle<WasmScript>> {
+            let path = format!("scripts/{}.delivery.wasm", name);
+            Ok(battle
+                .load_script(path.clone(), &path, ScriptRole::Delivery)?
+                .0)
+        };
+        let delivery_scripts = DeliveryScripts {
+            field_spot: delivery_script("field_spot")?,
+            market: delivery_script("market")?,
+            recruitment: delivery_script("recruitment")?,
+            practice_field: delivery_script("practice_field")?,
+            archery_field: delivery_script("archery_field")?,
+            child_spot: delivery_script("child_spot")?,
+            staging: delivery_script("staging")?,
+            deliver_troop_buffs: delivery_script("deliver_troop_buffs")?,
+            deliver_enemy: delivery_script("deliver_enemy")?,
+            deliver_king: delivery_script("deliver_king")?,
+            workshop: delivery_script("workshop")?,
+            tower_foundation: delivery_script("tower_foundation")?,
+        };
+
+        battle
+            .app
+            .insert_resource(TroopTypes(troop_types))
+            .insert_resource(AttackTypes(attack_types))
+            .insert_resource(HarvestableTypes(harvestable_types))
+            .insert_resource(StatTypes(stat_types))
+            .insert_resource(waves)
+            .insert_resource(delivery_scripts)
+            .insert_resource(FontAssets {
+                fira_sans: Handle::default(),
+            })
+            .insert_resource(TextureAssets {
+                harvest_base: Handle::default(),
+                locations: Handle::default(),
+                harvestables: Handle::default(),
+                troops: Handle::default(),
+                attacks: Handle::default(),
+            });
+        battle.app.update();
+        Ok(battle)
+    }
+
+    pub fn world(&mut self) -> &mut World {
+        &mut self.app.world
+    }
+
+    /// Compiles and checks the script at `path`, returning it with its exports.
+    fn load_script(
+        &mut self,
+        name: String,
+        path: &str,
+        role: ScriptRole,
+    ) -> anyhow::Result<(Handle<WasmScript>, Vec<String>)> {
+        let bytes = compile_script(path, fs::read(asset_path(path))?)?;
+        let exports = validate_script(path, role, &bytes)?;
+        Ok((self.add_script(name, bytes), exports))
+    }
+
+    /// Adds compiled wasm. It can be called once the next `update` or `step` has instantiated it.
+    pub fn add_script(&mut self, name: String, wasm_bytes: Vec<u8>) -> Handle<WasmScript> {
+        self.app
+            .world
+            .resource_mut::<Assets<WasmScript>>()
+            .add(WasmScript::Loaded(name, wasm_bytes))
+    }
+
+    /// Runs one `BATTLE_TIMESTEP` of the battle, then a frame to apply what it spawned.
+    pub fn step(&mut self) {
+        self.app.world.resource_mut::<BattleClock>().steps += 1;
+        self.battle_stage.run(&mut self.app.world);
+        self.app.update();
+    }
+
+    /// Despawns everything and goes back to the idle wave, keeping the loaded definitions and
+    /// scripts.
+    pub fn reset(&mut self) {
+        let entities: Vec<Entity> = self
+            .app
+            .world
+            .query::<Entity>()
+            .iter(&self.app.world)
+            .collect();
+        for entity in entities {
+            self.app.world.despawn(entity);
+        }
+        self.app
+            .world
+            .resource_mut::<AttackTypes>()
+            .clear_scripted();
+        self.app.insert_resource(CurrentWave::default());
+        self.app.insert_resource(InvasionSpots::default());
+    }
+
+    pub fn spawn(&mut self, f: impl FnOnce(&mut Commands) -> Entity) -> Entity {
+        let mut queue = CommandQueue::default();
+        let entity = f(&mut Commands::new(&mut queue, &self.app.world));
+        queue.apply(&mut self.app.world);
+        entity
+    }
+
+    pub fn spawn_troop(
+        &mut self,
+        troop_type: TroopType,
+        faction: Faction,
+        position: Vec2,
+    ) -> Entity {
+        let scripts = self.app.world.resource::<DeliveryScripts>();
+        let dropoff_script = if faction.faction_id == Faction::player().faction_id {
+            scripts.deliver_troop_buffs.clone()
+        } else {
+            scripts.deliver_enemy.clone()
+        };
+        self.spawn(|commands| {
+            spawn_troop(
+                commands,
+                position,
+                Handle::default(),
+                Handle::default(),
+                dropoff_script,
+                troop_type,
+                faction,
+                HashMap::default(),
+            )
+        })
+    }
+
+    pub fn staging_spot(&mut self, position: Vec2) -> Entity {
+        let script = self.app.world.resource::<DeliveryScripts>().staging.clone();
+        self.app
+            .world
+            .spawn((
+                Transform::from_translation(position.extend(1.)),
+                GlobalTransform::from_translation(position.extend(1.)),
+                Faction::player(),
+                StagingLocation::default(),
+                DeliveryDropoff::new(script),
+                Structure::new(15),
+            ))
+            .id()
+    }
+}
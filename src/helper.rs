@@ -5,9 +5,10 @@ use bevy::{
 
 use crate::{
     battle::{Faction, Troop},
-    common_scripting::{ScriptValues, ATTACK_SPEED_MOD_ID, DODGE_CHANCE_ID, SPEED_MOD_ID},
+    common_scripting::ScriptValues,
     harvest::HarvestSpot,
     loading::FontAssets,
+    stats::StatTypes,
     GameState,
 };
 
@@ -109,6 +110,7 @@ pub fn helper_text_system(
 
 pub fn helper_troop_system(
     fonts: Res<FontAssets>,
+    stat_types: Res<StatTypes>,
     mut helper_texts: Query<(&mut Text, &Parent), With<HelperText>>,
     troops: Query<(&Troop, &Faction, &ScriptValues)>,
 ) {
@@ -141,39 +143,24 @@ pub fn helper_troop_system(
                     font_size: 12.,
                 },
             ));
-            if let Some(speed_buff) = script_values.0.get(&SPEED_MOD_ID) {
-                let speed_buff_count = ((speed_buff - 1.) / 0.1) as i32;
-                description.push(TextSection::new(
-                    format!("MOV {}\n", speed_buff_count),
-                    TextStyle {
-                        color: Color::BLUE,
-                        font: fonts.fira_sans.clone(),
-                        font_size: 14.,
-                    },
-                ));
-            }
-            if let Some(speed_buff) = script_values.0.get(&ATTACK_SPEED_MOD_ID) {
-                let speed_buff_count = ((1. - speed_buff) / 0.1) as i32;
-                description.push(TextSection::new(
-                    format!("ATK {}\n", speed_buff_count),
-                    TextStyle {
-                        color: Color::BLUE,
-                        font: fonts.fira_sans.clone(),
-                        font_size: 14.,
-                    },
-                ));
-            }
-            if let Some(dodge_chance) = script_values.0.get(&DODGE_CHANCE_ID) {
-                let dodge_chance_count = (dodge_chance / 0.05) as i32;
-                description.push(TextSection::new(
-                    format!("DODGE {}", dodge_chance_count),
-                    TextStyle {
-                        color: Color::BLUE,
-                        font: fonts.fira_sans.clone(),
-                        font_size: 14.,
-                    },
-                ));
-            }
+            let stat_lines: Vec<String> = stat_types
+                .sorted()
+                .into_iter()
+                .filter_map(|stat| {
+                    script_values
+                        .0
+                        .get(&stat.id)
+                        .map(|value| stat.describe(*value))
+                })
+                .collect();
+            description.push(TextSection::new(
+                stat_lines.join("\n"),
+                TextStyle {
+                    color: Color::BLUE,
+                    font: fonts.fira_sans.clone(),
+                    font_size: 14.,
+                },
+            ));
             *text = Text::from_sections(description);
         }
     }
@@ -0,0 +1,345 @@
+use bevy::{asset::HandleId, prelude::*, utils::HashMap};
+use bevy_wasm_scripting::WasmScript;
+
+use crate::{
+    attacks::{AttackType, AttackTypes},
+    battle::{Troop, TroopType, TroopTypes},
+    delivery::{DeliveryDropoff, DeliverySource},
+    harvest::{HarvestSpot, Harvestable, HarvestableType, HarvestableTypes},
+    loading::DeliveryScripts,
+    script_abi::{validate_script, ScriptRole},
+    stats::{StatType, StatTypes},
+    wave::{Wave, Waves},
+    GameState,
+};
+
+/// Picks up edited scripts, troops, attacks, harvestables, stats and waves while the game runs.
+/// Assets are only watched when built with the `hot-reload` feature.
+pub struct HotReloadPlugin;
+
+impl Plugin for HotReloadPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<HotReload>()
+            .add_system_set(SystemSet::on_enter(GameState::Menu).with_system(watch_scripts))
+            .add_system(script_reload_system)
+            .add_system(troop_type_reload_system)
+            .add_system(attack_type_reload_system)
+            .add_system(harvestable_type_reload_system)
+            .add_system(stat_type_reload_system)
+            .add_system(wave_reload_system);
+    }
+}
+
+/// Scripts are instantiated per handle, so a changed script is copied into a fresh handle and
+/// everything using the old one moves over. This maps each watched script to the handle
+/// currently standing in for it.
+#[derive(Resource, Default)]
+pub struct HotReload {
+    watched: Vec<Handle<WasmScript>>,
+    current: HashMap<HandleId, Handle<WasmScript>>,
+}
+
+impl HotReload {
+    fn watch(&mut self, watched: Handle<WasmScript>, current: Handle<WasmScript>) {
+        self.current.insert(watched.id, current);
+        self.watched.push(watched);
+    }
+
+    fn replace(&mut self, old: &Handle<WasmScript>, new: &Handle<WasmScript>) {
+        for current in self.current.values_mut() {
+            if current == old {
+                *current = new.clone();
+            }
+        }
+    }
+}
+
+fn watch_scripts(
+    mut hot_reload: ResMut<HotReload>,
+    asset_server: Res<AssetServer>,
+    troop_types: Res<TroopTypes>,
+    mut delivery_scripts: ResMut<DeliveryScripts>,
+) {
+    if !hot_reload.watched.is_empty() {
+        return;
+    }
+    // The troop loader only reads its script's bytes, so the script file is loaded on its own
+    // to notice when it changes.
+    for troop_type in troop_types.0.values() {
+        if let Some(script) = &troop_type.script {
+            hot_reload.watch(
+                asset_server.load(troop_type.script_path.as_str()),
+                script.clone(),
+            );
+        }
+    }
+    for script in delivery_scripts.all_mut() {
+        hot_reload.watch(script.clone(), script.clone());
+    }
+}
+
+// Copies the bytes of `changed` into a new handle, under the name `current` was loaded with.
+fn fresh_script(
+    scripts: &mut Assets<WasmScript>,
+    changed: &Handle<WasmScript>,
+    current: &Handle<WasmScript>,
+) -> Option<(Handle<WasmScript>, String, Vec<u8>)> {
+    let bytes = match scripts.get(changed) {
+        Some(WasmScript::Loaded(_name, bytes)) => bytes.clone(),
+        _ => return None,
+    };
+    let name = match scripts.get(current) {
+        Some(WasmScript::Loaded(name, _bytes)) => name.clone(),
+        _ => return None,
+    };
+    Some((
+        scripts.add(WasmScript::Loaded(name.clone(), bytes.clone())),
+        name,
+        bytes,
+    ))
+}
+
+fn replace_troop_script(
+    troop_type: &mut TroopType,
+    old: &Handle<WasmScript>,
+    new: &Handle<WasmScript>,
+    exports: &[String],
+) {
+    if troop_type.script.as_ref() == Some(old) {
+        troop_type.script = Some(new.clone());
+        troop_type.exports = exports.to_vec();
+    }
+}
+
+fn script_reload_system(
+    mut hot_reload: ResMut<HotReload>,
+    mut script_events: EventReader<AssetEvent<WasmScript>>,
+    mut scripts: ResMut<Assets<WasmScript>>,
+    troop_types: Option<ResMut<TroopTypes>>,
+    delivery_scripts: Option<ResMut<DeliveryScripts>>,
+    mut troops: Query<&mut Troop>,
+    mut sources: Query<&mut DeliverySource>,
+    mut dropoffs: Query<&mut DeliveryDropoff>,
+) {
+    let (mut troop_types, mut delivery_scripts) = match (troop_types, delivery_scripts) {
+        (Some(troop_types), Some(delivery_scripts)) => (troop_types, delivery_scripts),
+        _ => return,
+    };
+    for event in script_events.iter() {
+        let changed = match event {
+            AssetEvent::Modified { handle } => handle,
+            _ => continue,
+        };
+        let current = match hot_reload.current.get(&changed.id) {
+            Some(current) => current.clone(),
+            None => continue,
+        };
+        let (fresh, name, bytes) = match fresh_script(&mut scripts, changed, &current) {
+            Some(fresh) => fresh,
+            None => continue,
+        };
+        let role = if delivery_scripts
+            .all_mut()
+            .into_iter()
+            .any(|script| *script == current)
+        {
+            ScriptRole::Delivery
+        } else {
+            ScriptRole::Troop
+        };
+        let exports = match validate_script(&name, role, &bytes) {
+            Ok(exports) => exports,
+            Err(err) => {
+                warn!("Not reloading a broken script: {}", err);
+                continue;
+            }
+        };
+        for troop_type in troop_types.0.values_mut() {
+            replace_troop_script(troop_type, &current, &fresh, &exports);
+        }
+        for mut troop in troops.iter_mut() {
+            replace_troop_script(&mut troop.troop_type, &current, &fresh, &exports);
+        }
+        for mut source in sources.iter_mut() {
+            if source.script == current {
+                source.script = fresh.clone();
+            }
+        }
+        for mut dropoff in dropoffs.iter_mut() {
+            if dropoff.script == current {
+                dropoff.script = fresh.clone();
+            }
+        }
+        for script in delivery_scripts.all_mut() {
+            if *script == current {
+                *script = fresh.clone();
+            }
+        }
+        hot_reload.replace(&current, &fresh);
+        info!("Reloaded script {:?}", changed.id);
+    }
+}
+
+fn troop_type_reload_system(
+    mut hot_reload: ResMut<HotReload>,
+    mut troop_type_events: EventReader<AssetEvent<TroopType>>,
+    troop_type_assets: Res<Assets<TroopType>>,
+    mut scripts: ResMut<Assets<WasmScript>>,
+    troop_types: Option<ResMut<TroopTypes>>,
+    mut troops: Query<&mut Troop>,
+) {
+    let mut troop_types = match troop_types {
+        Some(troop_types) => troop_types,
+        None => return,
+    };
+    for event in troop_type_events.iter() {
+        let mut troop_type = match event {
+            AssetEvent::Modified { handle } => match troop_type_assets.get(handle) {
+                Some(troop_type) => troop_type.clone(),
+                None => continue,
+            },
+            _ => continue,
+        };
+        let previous = troop_types
+            .get(troop_type.id)
+            .and_then(|previous| previous.script);
+        // The loader rewrote the script under the same label, so it needs a fresh handle too.
+        if let Some((fresh, name, bytes)) = troop_type
+            .script
+            .as_ref()
+            .and_then(|script| fresh_script(&mut scripts, script, script))
+        {
+            troop_type.exports = match validate_script(&name, ScriptRole::Troop, &bytes) {
+                Ok(exports) => exports,
+                Err(err) => {
+                    warn!("Not reloading troop {}: {}", troop_type.name, err);
+                    continue;
+                }
+            };
+            if let Some(previous) = &previous {
+                hot_reload.replace(previous, &fresh);
+            }
+            troop_type.script = Some(fresh);
+        }
+        // Live troops take the new stats, but a troop running a script of its own keeps it.
+        for mut troop in troops.iter_mut() {
+            if troop.troop_type.id == troop_type.id {
+                let (script, exports) = if troop.troop_type.script == previous {
+                    (troop_type.script.clone(), troop_type.exports.clone())
+                } else {
+                    (
+                        troop.troop_type.script.take(),
+                        std::mem::take(&mut troop.troop_type.exports),
+                    )
+                };
+                troop.health = troop.health.min(troop_type.health);
+                troop.troop_type = TroopType {
+                    script,
+                    exports,
+                    ..troop_type.clone()
+                };
+            }
+        }
+        info!("Reloaded troop {}", troop_type.name);
+        troop_types.0.insert(troop_type.id, troop_type);
+    }
+}
+
+fn attack_type_reload_system(
+    mut attack_type_events: EventReader<AssetEvent<AttackType>>,
+    attack_type_assets: Res<Assets<AttackType>>,
+    attack_types: Option<ResMut<AttackTypes>>,
+) {
+    let mut attack_types = match attack_types {
+        Some(attack_types) => attack_types,
+        None => return,
+    };
+    for event in attack_type_events.iter() {
+        if let AssetEvent::Modified { handle } = event {
+            if let Some(attack_type) = attack_type_assets.get(handle) {
+                info!("Reloaded attack {}", attack_type.id);
+                attack_types.0.insert(attack_type.id, attack_type.clone());
+            }
+        }
+    }
+}
+
+fn harvestable_type_reload_system(
+    mut harvestable_type_events: EventReader<AssetEvent<HarvestableType>>,
+    harvestable_type_assets: Res<Assets<HarvestableType>>,
+    harvestable_types: Option<ResMut<HarvestableTypes>>,
+    mut spots: Query<&mut HarvestSpot>,
+    mut harvestables: Query<&mut Harvestable>,
+) {
+    let mut harvestable_types = match harvestable_types {
+        Some(harvestable_types) => harvestable_types,
+        None => return,
+    };
+    for event in harvestable_type_events.iter() {
+        let harvestable_type = match event {
+            AssetEvent::Modified { handle } => match harvestable_type_assets.get(handle) {
+                Some(harvestable_type) => harvestable_type,
+                None => continue,
+            },
+            _ => continue,
+        };
+        for mut spot in spots.iter_mut() {
+            if spot
+                .harvestable_type
+                .as_ref()
+                .map_or(false, |growing| growing.id == harvestable_type.id)
+            {
+                spot.harvest_time = harvestable_type.base_harvest_time;
+                spot.harvestable_type = Some(harvestable_type.clone());
+            }
+        }
+        for mut harvestable in harvestables.iter_mut() {
+            if harvestable.0.id == harvestable_type.id {
+                harvestable.0 = harvestable_type.clone();
+            }
+        }
+        info!("Reloaded harvestable {}", harvestable_type.name);
+        harvestable_types
+            .0
+            .insert(harvestable_type.id, harvestable_type.clone());
+    }
+}
+
+fn stat_type_reload_system(
+    mut stat_type_events: EventReader<AssetEvent<StatType>>,
+    stat_type_assets: Res<Assets<StatType>>,
+    stat_types: Option<ResMut<StatTypes>>,
+) {
+    let mut stat_types = match stat_types {
+        Some(stat_types) => stat_types,
+        None => return,
+    };
+    for event in stat_type_events.iter() {
+        if let AssetEvent::Modified { handle } = event {
+            if let Some(stat_type) = stat_type_assets.get(handle) {
+                info!("Reloaded stat {}", stat_type.key);
+                stat_types.0.insert(stat_type.id, stat_type.clone());
+            }
+        }
+    }
+}
+
+// The wave in progress keeps its copy, edits apply from the next one.
+fn wave_reload_system(
+    mut wave_events: EventReader<AssetEvent<Wave>>,
+    wave_assets: Res<Assets<Wave>>,
+    waves: Option<ResMut<Waves>>,
+) {
+    let mut waves = match waves {
+        Some(waves) => waves,
+        None => return,
+    };
+    for event in wave_events.iter() {
+        if let AssetEvent::Modified { handle } = event {
+            if let Some(wave) = wave_assets.get(handle) {
+                info!("Reloaded wave {}", wave.id);
+                waves.0.insert(wave.id, wave.clone());
+            }
+        }
+    }
+}
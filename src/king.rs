@@ -0,0 +1,258 @@
+use bevy::prelude::*;
+use bevy_wasm_scripting::*;
+use serde::Deserialize;
+
+use crate::{
+    battle::{Troop, TroopTypes, KING_ID},
+    common_scripting::{disable_export, DisabledExports, Quarantined},
+    loading::FontAssets,
+    script_scope::{with_scope, ScriptCategory},
+    simulation::{BattleClock, BattleStage},
+    GameState,
+};
+
+// Abilities are bound to these keys in the order the king's troop file lists them.
+pub const ABILITY_KEYS: [(KeyCode, &str); 3] =
+    [(KeyCode::Q, "Q"), (KeyCode::W, "W"), (KeyCode::E, "E")];
+
+pub struct KingPlugin;
+
+impl Plugin for KingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<KingAbilities>()
+            .add_system_set(
+                SystemSet::on_enter(GameState::Playing).with_system(setup_king_abilities),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing).with_system(king_ability_input_system),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing).with_system(king_ability_button_system),
+            )
+            .add_system_set_to_stage(
+                BattleStage,
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(ScriptSystemWithCommands::<_, Troop>::wrap(
+                        IntoSystem::into_system(king_ability_system),
+                    ))
+                    .with_system(king_ability_cooldown_system),
+            );
+    }
+}
+
+/// An active ability of the king. The king's script implements it in its `king_ability` export.
+#[derive(Clone, Deserialize)]
+pub struct KingAbility {
+    pub id: i32,
+    pub name: String,
+    pub cooldown: f32,
+}
+
+#[derive(Resource, Default)]
+pub struct KingAbilities {
+    pub cooldowns: Vec<f32>,
+    triggered: Vec<usize>,
+}
+
+impl KingAbilities {
+    pub fn trigger(&mut self, slot: usize) {
+        if !self.triggered.contains(&slot) {
+            self.triggered.push(slot);
+        }
+    }
+
+    pub fn is_ready(&self, slot: usize) -> bool {
+        self.cooldowns.get(slot).cloned().unwrap_or(0.) <= 0.
+    }
+}
+
+const BUTTON_READY: Color = Color::rgb(0.15, 0.15, 0.15);
+const BUTTON_HOVERED: Color = Color::rgb(0.25, 0.25, 0.25);
+const BUTTON_COOLING: Color = Color::rgb(0.4, 0.1, 0.1);
+
+#[derive(Component)]
+pub struct KingAbilityButton(pub usize);
+
+#[derive(Component)]
+pub struct KingAbilityText(pub usize);
+
+fn ability_label(slot: usize, ability: &KingAbility, cooldown: f32) -> String {
+    if cooldown > 0. {
+        format!(
+            "{} {} ({:.0})",
+            ABILITY_KEYS[slot].1,
+            ability.name,
+            cooldown.ceil()
+        )
+    } else {
+        format!("{} {}", ABILITY_KEYS[slot].1, ability.name)
+    }
+}
+
+fn king_abilities(troop_types: &TroopTypes) -> Vec<KingAbility> {
+    troop_types
+        .get(KING_ID)
+        .map(|king| king.abilities)
+        .unwrap_or_default()
+        .into_iter()
+        .take(ABILITY_KEYS.len())
+        .collect()
+}
+
+fn setup_king_abilities(
+    mut commands: Commands,
+    fonts: Res<FontAssets>,
+    troop_types: Res<TroopTypes>,
+    mut abilities: ResMut<KingAbilities>,
+) {
+    let king_abilities = king_abilities(&troop_types);
+    *abilities = KingAbilities {
+        cooldowns: vec![0.; king_abilities.len()],
+        triggered: Vec::new(),
+    };
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    bottom: Val::Px(8.),
+                    left: Val::Px(8.),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .with_children(|parent| {
+            for (slot, ability) in king_abilities.iter().enumerate() {
+                parent
+                    .spawn((
+                        ButtonBundle {
+                            style: Style {
+                                size: Size::new(Val::Px(120.), Val::Px(24.)),
+                                margin: UiRect::all(Val::Px(2.)),
+                                justify_content: JustifyContent::Center,
+                                align_items: AlignItems::Center,
+                                ..Default::default()
+                            },
+                            background_color: BUTTON_READY.into(),
+                            ..Default::default()
+                        },
+                        KingAbilityButton(slot),
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn((
+                            TextBundle::from_section(
+                                ability_label(slot, ability, 0.),
+                                TextStyle {
+                                    font: fonts.fira_sans.clone(),
+                                    font_size: 14.,
+                                    color: Color::rgb(0.9, 0.9, 0.9),
+                                },
+                            ),
+                            KingAbilityText(slot),
+                        ));
+                    });
+            }
+        });
+}
+
+fn king_ability_input_system(
+    mut abilities: ResMut<KingAbilities>,
+    input: Res<Input<KeyCode>>,
+    interaction_query: Query<(&Interaction, &KingAbilityButton), Changed<Interaction>>,
+) {
+    for (slot, (key, _label)) in ABILITY_KEYS.iter().enumerate() {
+        if input.just_pressed(*key) {
+            abilities.trigger(slot);
+        }
+    }
+    for (interaction, button) in interaction_query.iter() {
+        if *interaction == Interaction::Clicked {
+            abilities.trigger(button.0);
+        }
+    }
+}
+
+fn king_ability_button_system(
+    abilities: Res<KingAbilities>,
+    troop_types: Res<TroopTypes>,
+    mut buttons: Query<(&Interaction, &KingAbilityButton, &mut BackgroundColor)>,
+    mut texts: Query<(&KingAbilityText, &mut Text)>,
+) {
+    for (interaction, button, mut color) in buttons.iter_mut() {
+        *color = if !abilities.is_ready(button.0) {
+            BUTTON_COOLING.into()
+        } else if *interaction == Interaction::Hovered {
+            BUTTON_HOVERED.into()
+        } else {
+            BUTTON_READY.into()
+        };
+    }
+    let king_abilities = king_abilities(&troop_types);
+    for (ability_text, mut text) in texts.iter_mut() {
+        if let (Some(ability), Some(cooldown)) = (
+            king_abilities.get(ability_text.0),
+            abilities.cooldowns.get(ability_text.0),
+        ) {
+            text.sections[0].value = ability_label(ability_text.0, ability, *cooldown);
+        }
+    }
+}
+
+fn king_ability_cooldown_system(mut abilities: ResMut<KingAbilities>, clock: Res<BattleClock>) {
+    for cooldown in abilities.cooldowns.iter_mut() {
+        if *cooldown > 0. {
+            *cooldown -= clock.delta_seconds();
+        }
+    }
+}
+
+// The script reports whether the ability went off, so a wasted press doesn't start the cooldown.
+fn king_ability_system(
+    mut commands: Commands,
+    mut script_env: WasmScriptComponentEnv<Troop, ()>,
+    mut abilities: ResMut<KingAbilities>,
+    troops: Query<(Entity, &Troop, Option<&DisabledExports>), Without<Quarantined>>,
+) {
+    let triggered: Vec<usize> = abilities.triggered.drain(..).collect();
+    if let Some((entity, king, disabled)) = troops
+        .iter()
+        .find(|(_entity, troop, _disabled)| troop.troop_type.id == KING_ID)
+    {
+        if !DisabledExports::allows(disabled, "king_ability") {
+            return;
+        }
+        for slot in triggered {
+            if !abilities.is_ready(slot) {
+                continue;
+            }
+            if let Some(ability) = king.troop_type.abilities.get(slot) {
+                match with_scope(ScriptCategory::Ability, &[entity], || {
+                    script_env.call_if_instantiated_2::<f64, i32, i8>(
+                        king.get_wasm_script_handle(),
+                        "king_ability",
+                        EntityId::from_entity(entity),
+                        ability.id,
+                    )
+                }) {
+                    Ok(used) => {
+                        if let (1, Some(cooldown)) = (used, abilities.cooldowns.get_mut(slot)) {
+                            *cooldown = ability.cooldown;
+                        }
+                    }
+                    Err(err) => {
+                        disable_export(
+                            &mut commands,
+                            entity,
+                            king.get_wasm_script_handle(),
+                            "king_ability",
+                            err,
+                        );
+                        break;
+                    }
+                }
+            }
+        }
+    }
+}
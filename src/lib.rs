@@ -1,16 +1,36 @@
 mod attacks;
 mod audio;
+mod balance;
 mod battle;
 mod battle_scripting;
+mod combat_log;
 mod common_scripting;
 mod delivery;
 mod delivery_scripting;
+mod director;
+mod director_scripting;
 mod harvest;
+mod headless;
 mod helper;
+mod hot_reload;
+mod king;
 mod loading;
 mod market;
 mod menu;
 mod recruiting;
+mod scenario;
+mod script_abi;
+mod script_errors;
+mod script_fuel;
+mod script_profiler;
+mod script_scope;
+#[cfg(test)]
+mod script_testing;
+mod simulation;
+mod speed;
+mod stats;
+mod structure;
+mod tower;
 mod wave;
 
 use crate::audio::InternalAudioPlugin;
@@ -23,12 +43,28 @@ use battle::BattlePlugin;
 use bevy::diagnostic::{FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin};
 use bevy::prelude::*;
 use bevy::{app::App, ecs::system::Command};
+use combat_log::CombatLogPlugin;
 use delivery::DeliveryPlugin;
+use director::DirectorPlugin;
 use helper::{helper_text_system, HelperPlugin};
+use hot_reload::HotReloadPlugin;
+use king::KingPlugin;
 use market::MarketPlugin;
+use rand::{rngs::StdRng, Rng, SeedableRng};
 use recruiting::RecruitingPlugin;
+use scenario::ScenarioPlugin;
+use script_errors::ScriptErrorPlugin;
+use script_fuel::ScriptFuelPlugin;
+use script_profiler::ScriptProfilerPlugin;
+use simulation::SimulationPlugin;
+use speed::SpeedPlugin;
+use stats::StatPlugin;
+use structure::StructurePlugin;
+use tower::TowerPlugin;
 use wave::WavePlugin;
 
+pub use balance::{run_balance_sweep, BALANCE_SWEEP_ENV};
+
 // This example game uses States to separate logic
 // See https://bevy-cheatbook.github.io/programming/states.html
 // Or https://github.com/bevyengine/bevy/blob/main/examples/ecs/state.rs
@@ -47,16 +83,30 @@ pub struct GamePlugin;
 impl Plugin for GamePlugin {
     fn build(&self, app: &mut App) {
         app.add_state(GameState::Loading)
+            .init_resource::<GameRng>()
+            .add_plugin(SimulationPlugin)
             .add_plugin(LoadingPlugin)
             .add_plugin(MenuPlugin)
             .add_plugin(InternalAudioPlugin)
             .add_plugin(BattlePlugin)
+            .add_plugin(KingPlugin)
+            .add_plugin(CombatLogPlugin)
             .add_plugin(DeliveryPlugin)
             .add_plugin(MarketPlugin)
             .add_plugin(HarvestPlugin)
             .add_plugin(RecruitingPlugin)
             .add_plugin(WavePlugin)
+            .add_plugin(ScenarioPlugin)
+            .add_plugin(DirectorPlugin)
+            .add_plugin(SpeedPlugin)
+            .add_plugin(StructurePlugin)
+            .add_plugin(TowerPlugin)
+            .add_plugin(StatPlugin)
             .add_plugin(HelperPlugin)
+            .add_plugin(ScriptErrorPlugin)
+            .add_plugin(ScriptFuelPlugin)
+            .add_plugin(ScriptProfilerPlugin)
+            .add_plugin(HotReloadPlugin)
             .add_system_set(SystemSet::on_exit(GameState::Playing).with_system(
                 |mut command: Commands, entities: Query<Entity>| {
                     for entity in entities.iter() {
@@ -72,6 +122,33 @@ impl Plugin for GamePlugin {
         }
     }
 }
+/// All gameplay randomness goes through this, so a battle can be replayed from a seed.
+#[derive(Resource)]
+pub struct GameRng(pub StdRng);
+
+impl Default for GameRng {
+    fn default() -> Self {
+        Self(StdRng::from_entropy())
+    }
+}
+
+impl GameRng {
+    pub fn reseed(&mut self, seed: u64) {
+        self.0 = StdRng::seed_from_u64(seed);
+    }
+
+    pub fn random(&mut self) -> f32 {
+        self.0.gen::<f32>()
+    }
+}
+
+pub fn world_random(world: &mut World) -> f32 {
+    world
+        .get_resource_mut::<GameRng>()
+        .map(|mut rng| rng.random())
+        .unwrap_or_else(rand::random::<f32>)
+}
+
 pub struct SafeInsert<T> {
     pub entity: Entity,
     pub bundle: T,
@@ -2,6 +2,8 @@ use crate::{
     attacks::{AttackType, AttackTypes},
     battle::{TroopType, TroopTypes},
     harvest::{Harvestable, HarvestableType, HarvestableTypes},
+    scenario::{Scenario, Scenarios},
+    stats::{StatType, StatTypes},
     wave::{Wave, Waves},
     GameState,
 };
@@ -25,12 +27,16 @@ impl Plugin for LoadingPlugin {
                 .with_collection::<DeliveryScripts>()
                 .with_collection::<HarvestableAssets>()
                 .with_collection::<AttackAssets>()
+                .with_collection::<StatAssets>()
                 .with_collection::<TroopAssets>()
                 .with_collection::<WaveAssets>()
+                .with_collection::<ScenarioAssets>()
                 .init_resource::<HarvestableTypes>()
                 .init_resource::<Waves>()
                 .init_resource::<TroopTypes>()
                 .init_resource::<AttackTypes>()
+                .init_resource::<StatTypes>()
+                .init_resource::<Scenarios>()
                 .continue_to_state(GameState::Menu),
         );
     }
@@ -69,26 +75,49 @@ pub struct AudioAssets {
 
 #[derive(AssetCollection, Resource)]
 pub struct DeliveryScripts {
-    #[asset(path = "scripts/field_spot.wasm")]
+    #[asset(path = "scripts/field_spot.delivery.wasm")]
     pub field_spot: Handle<WasmScript>,
-    #[asset(path = "scripts/market.wasm")]
+    #[asset(path = "scripts/market.delivery.wasm")]
     pub market: Handle<WasmScript>,
-    #[asset(path = "scripts/recruitment.wasm")]
+    #[asset(path = "scripts/recruitment.delivery.wasm")]
     pub recruitment: Handle<WasmScript>,
-    #[asset(path = "scripts/practice_field.wasm")]
+    #[asset(path = "scripts/practice_field.delivery.wasm")]
     pub practice_field: Handle<WasmScript>,
-    #[asset(path = "scripts/archery_field.wasm")]
+    #[asset(path = "scripts/archery_field.delivery.wasm")]
     pub archery_field: Handle<WasmScript>,
-    #[asset(path = "scripts/child_spot.wasm")]
+    #[asset(path = "scripts/child_spot.delivery.wasm")]
     pub child_spot: Handle<WasmScript>,
-    #[asset(path = "scripts/staging.wasm")]
+    #[asset(path = "scripts/staging.delivery.wasm")]
     pub staging: Handle<WasmScript>,
-    #[asset(path = "scripts/deliver_troop_buffs.wasm")]
+    #[asset(path = "scripts/deliver_troop_buffs.delivery.wasm")]
     pub deliver_troop_buffs: Handle<WasmScript>,
-    #[asset(path = "scripts/deliver_enemy.wasm")]
+    #[asset(path = "scripts/deliver_enemy.delivery.wasm")]
     pub deliver_enemy: Handle<WasmScript>,
-    #[asset(path = "scripts/deliver_king.wasm")]
+    #[asset(path = "scripts/deliver_king.delivery.wasm")]
     pub deliver_king: Handle<WasmScript>,
+    #[asset(path = "scripts/workshop.delivery.wasm")]
+    pub workshop: Handle<WasmScript>,
+    #[asset(path = "scripts/tower_foundation.delivery.wasm")]
+    pub tower_foundation: Handle<WasmScript>,
+}
+
+impl DeliveryScripts {
+    pub fn all_mut(&mut self) -> [&mut Handle<WasmScript>; 12] {
+        [
+            &mut self.field_spot,
+            &mut self.market,
+            &mut self.recruitment,
+            &mut self.practice_field,
+            &mut self.archery_field,
+            &mut self.child_spot,
+            &mut self.staging,
+            &mut self.deliver_troop_buffs,
+            &mut self.deliver_enemy,
+            &mut self.deliver_king,
+            &mut self.workshop,
+            &mut self.tower_foundation,
+        ]
+    }
 }
 
 #[derive(AssetCollection, Resource)]
@@ -102,6 +131,7 @@ pub struct HarvestableAssets {
             "harvestables/red_berry.harvest",
             "harvestables/pear.harvest",
             "harvestables/lemon.harvest",
+            "harvestables/blueprint.harvest",
         ),
         collection(typed)
     )]
@@ -128,6 +158,20 @@ pub struct WaveAssets {
     pub waves: Vec<Handle<Wave>>,
 }
 
+#[derive(AssetCollection, Resource)]
+pub struct ScenarioAssets {
+    #[asset(
+        paths(
+            "scenarios/default.scenario",
+            "scenarios/harvest_festival.scenario",
+            "scenarios/warlord.scenario",
+            "scenarios/siege.scenario",
+        ),
+        collection(typed)
+    )]
+    pub scenarios: Vec<Handle<Scenario>>,
+}
+
 #[derive(AssetCollection, Resource)]
 pub struct AttackAssets {
     #[asset(
@@ -144,6 +188,20 @@ pub struct AttackAssets {
     pub attacks: Vec<Handle<AttackType>>,
 }
 
+#[derive(AssetCollection, Resource)]
+pub struct StatAssets {
+    #[asset(
+        paths(
+            "stats/speed.stat",
+            "stats/attack_speed.stat",
+            "stats/attack_range.stat",
+            "stats/dodge_chance.stat",
+        ),
+        collection(typed)
+    )]
+    pub stats: Vec<Handle<StatType>>,
+}
+
 #[derive(AssetCollection, Resource)]
 pub struct TroopAssets {
     #[asset(
@@ -153,6 +211,7 @@ pub struct TroopAssets {
             "troops/ranger.troop",
             "troops/warrior.troop",
             "troops/king.troop",
+            "troops/tower.troop",
         ),
         collection(typed)
     )]
@@ -1,29 +1,44 @@
 // disable console on windows for release builds
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+use bevy::asset::AssetPlugin;
 use bevy::prelude::*;
 use bevy::window::WindowId;
 use bevy::winit::WinitWindows;
 use bevy::DefaultPlugins;
 use bevy_wasm_scripting::WasmPlugin;
-use harvests_of_war::GamePlugin;
+use harvests_of_war::{run_balance_sweep, GamePlugin, BALANCE_SWEEP_ENV};
 use std::io::Cursor;
 use winit::window::Icon;
 
 fn main() {
+    if let Ok(path) = std::env::var(BALANCE_SWEEP_ENV) {
+        if let Err(err) = run_balance_sweep(&path) {
+            eprintln!("Balance sweep {} failed: {}", path, err);
+            std::process::exit(1);
+        }
+        return;
+    }
     App::new()
         .insert_resource(Msaa { samples: 1 })
         .insert_resource(ClearColor(Color::rgb(0.4, 0.4, 0.4)))
-        .add_plugins(DefaultPlugins.set(WindowPlugin {
-            window: WindowDescriptor {
-                width: 948.,
-                height: 533.,
-                title: "Harvests of War".to_string(),
-                canvas: Some("#bevy".to_owned()),
-                ..Default::default()
-            },
-            ..default()
-        }))
+        .add_plugins(
+            DefaultPlugins
+                .set(WindowPlugin {
+                    window: WindowDescriptor {
+                        width: 948.,
+                        height: 533.,
+                        title: "Harvests of War".to_string(),
+                        canvas: Some("#bevy".to_owned()),
+                        ..Default::default()
+                    },
+                    ..default()
+                })
+                .set(AssetPlugin {
+                    watch_for_changes: cfg!(feature = "hot-reload"),
+                    ..default()
+                }),
+        )
         .add_plugin(WasmPlugin)
         .add_plugin(GamePlugin)
         .add_startup_system(set_window_icon)
@@ -1,6 +1,9 @@
 use bevy::prelude::*;
 
-use crate::{delivery::*, helper::HelperTextBundle, loading::*, GameState};
+use crate::{
+    battle::Faction, delivery::*, helper::HelperTextBundle, loading::*, structure::Structure,
+    GameState,
+};
 
 pub struct MarketPlugin;
 
@@ -16,6 +19,8 @@ pub struct MarketBundle {
     delivery_source: DeliverySource,
     delivery_location: DeliveryDropoff,
     delivery_anchor: DeliveryAnchor,
+    structure: Structure,
+    faction: Faction,
 }
 
 fn spawn_market(
@@ -44,6 +49,8 @@ fn spawn_market(
             delivery_anchor: DeliveryAnchor::new(0., -16., 32., 32 * 32),
             delivery_source: DeliverySource::new(scripts.market.clone()),
             delivery_location: DeliveryDropoff::new(scripts.market.clone()),
+            structure: Structure::new(30),
+            faction: Faction::player(),
         })
         .add_child(helper);
 }
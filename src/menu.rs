@@ -1,4 +1,5 @@
 use crate::loading::FontAssets;
+use crate::scenario::{Scenarios, SCENARIO_ENV};
 use crate::GameState;
 use bevy::prelude::*;
 
@@ -30,10 +31,15 @@ impl Default for ButtonColors {
     }
 }
 
+// Says which scenario Play starts, and how to pick another.
+#[derive(Component)]
+struct ScenarioHint;
+
 fn setup_menu(
     mut commands: Commands,
     font_assets: Res<FontAssets>,
     button_colors: Res<ButtonColors>,
+    scenarios: Res<Scenarios>,
 ) {
     commands.spawn(Camera2dBundle::default());
     commands
@@ -64,6 +70,40 @@ fn setup_menu(
                 ..Default::default()
             });
         });
+    let mut names: Vec<&String> = scenarios.0.keys().collect();
+    names.sort();
+    let selected = scenarios
+        .selected()
+        .map_or_else(|| "none".to_string(), |scenario| scenario.name);
+    commands.spawn((
+        TextBundle::from_section(
+            format!(
+                "Scenario: {}\nSet {} to one of: {}",
+                selected,
+                SCENARIO_ENV,
+                names
+                    .iter()
+                    .map(|name| name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            TextStyle {
+                font: font_assets.fira_sans.clone(),
+                font_size: 20.0,
+                color: Color::rgb(0.9, 0.9, 0.9),
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                left: Val::Px(10.0),
+                bottom: Val::Px(10.0),
+                ..Default::default()
+            },
+            ..Default::default()
+        }),
+        ScenarioHint,
+    ));
 }
 
 fn click_play_button(
@@ -89,6 +129,13 @@ fn click_play_button(
     }
 }
 
-fn cleanup_menu(mut commands: Commands, button: Query<Entity, With<Button>>) {
+fn cleanup_menu(
+    mut commands: Commands,
+    button: Query<Entity, With<Button>>,
+    hint: Query<Entity, With<ScenarioHint>>,
+) {
     commands.entity(button.single()).despawn_recursive();
+    for hint in hint.iter() {
+        commands.entity(hint).despawn_recursive();
+    }
 }
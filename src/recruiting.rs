@@ -1,7 +1,8 @@
 use bevy::prelude::*;
 
 use crate::{
-    delivery::*, harvest::spawn_harvest_spot, helper::HelperTextBundle, loading::*, GameState,
+    battle::Faction, delivery::*, harvest::spawn_harvest_spot, helper::HelperTextBundle,
+    loading::*, structure::Structure, GameState,
 };
 
 pub struct RecruitingPlugin;
@@ -23,6 +24,8 @@ pub struct RecruiterBundle {
     sprite: SpriteSheetBundle,
     delivery_source: DeliverySource,
     delivery_anchor: DeliveryAnchor,
+    structure: Structure,
+    faction: Faction,
 }
 
 #[derive(Bundle)]
@@ -30,6 +33,8 @@ pub struct TrainerBundle {
     sprite: SpriteSheetBundle,
     delivery_dropoff: DeliveryDropoff,
     delivery_anchor: DeliveryAnchor,
+    structure: Structure,
+    faction: Faction,
 }
 
 fn spawn_recruitment(
@@ -57,6 +62,8 @@ fn spawn_recruitment(
             },
             delivery_anchor: DeliveryAnchor::new(0., -16., 32., 32 * 32),
             delivery_source: DeliverySource::new(scripts.recruitment.clone()),
+            structure: Structure::new(20),
+            faction: Faction::player(),
         })
         .add_child(helper);
 }
@@ -110,6 +117,8 @@ fn spawn_training_ground(
             },
             delivery_anchor: DeliveryAnchor::new(0., -16., 32., 32 * 32),
             delivery_dropoff: DeliveryDropoff::new(scripts.practice_field.clone()),
+            structure: Structure::new(20),
+            faction: Faction::player(),
         })
         .add_child(spot_one)
         .add_child(spot_two)
@@ -166,6 +175,8 @@ fn spawn_archery_field(
             },
             delivery_anchor: DeliveryAnchor::new(0., -16., 32., 32 * 32),
             delivery_dropoff: DeliveryDropoff::new(scripts.archery_field.clone()),
+            structure: Structure::new(20),
+            faction: Faction::player(),
         })
         .add_child(spot_one)
         .add_child(spot_two)
@@ -0,0 +1,468 @@
+use bevy::{
+    asset::{AssetLoader, LoadedAsset},
+    prelude::*,
+    reflect::TypeUuid,
+    utils::HashMap,
+};
+use bevy_wasm_scripting::WasmScript;
+use serde::Deserialize;
+
+use crate::{
+    battle::{Faction, Troop, TroopTypes},
+    common_scripting::compile_script,
+    harvest::{CropHarvested, HarvestSpot},
+    loading::ScenarioAssets,
+    script_abi::{validate_script, ScriptRole},
+    simulation::{BattleClock, BattleStage},
+    structure::Structure,
+    wave::{CurrentWave, Waves},
+    GameState,
+};
+
+/// Set this environment variable to a scenario name to play it instead of the default.
+pub const SCENARIO_ENV: &str = "HARVESTS_SCENARIO";
+
+pub struct ScenarioPlugin;
+
+impl Plugin for ScenarioPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<Scenario>()
+            .init_asset_loader::<ScenarioAssetLoader>()
+            .init_resource::<ObjectiveProgress>()
+            .add_system_set(
+                SystemSet::on_enter(GameState::Playing).with_system(start_scenario_system),
+            )
+            .add_system_set_to_stage(
+                BattleStage,
+                SystemSet::on_update(GameState::Playing).with_system(objective_timer_system),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(objective_system)
+                    .with_system(boss_tagging_system.after(objective_system)),
+            );
+    }
+}
+
+#[derive(Clone, Deserialize)]
+pub enum Objective {
+    /// Lost once no allied troop of this type is left, whichever one that is.
+    ProtectType { troop_id: i32 },
+    /// Won once this many waves have been cleared, lost if the waves run out first.
+    SurviveWaves { waves: i32 },
+    /// Lost once fewer than this many field plots are standing.
+    KeepPlots { minimum: usize },
+    /// Won by harvesting this many crops, lost if the time runs out first.
+    HarvestCrops { crops: usize, time_limit: f32 },
+    /// Won once the boss dies, which is the first enemy troop of this type to arrive. Lost if the
+    /// waves run out without it.
+    KillBoss { troop_id: i32 },
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ObjectiveStatus {
+    InProgress,
+    Complete,
+    Failed,
+}
+
+// What the objectives are judged against, gathered once per frame.
+pub struct ObjectiveContext<'a> {
+    allied_troop_ids: Vec<i32>,
+    standing_plots: usize,
+    waves_cleared: i32,
+    living_bosses: Vec<i32>,
+    progress: &'a ObjectiveProgress,
+}
+
+impl Objective {
+    /// Whether finishing this objective counts towards winning, rather than only guarding a loss.
+    pub fn is_goal(&self) -> bool {
+        match self {
+            Objective::ProtectType { .. } | Objective::KeepPlots { .. } => false,
+            _ => true,
+        }
+    }
+
+    pub fn status(&self, context: &ObjectiveContext) -> ObjectiveStatus {
+        match self {
+            Objective::ProtectType { troop_id } => {
+                if context.allied_troop_ids.contains(troop_id) {
+                    ObjectiveStatus::InProgress
+                } else {
+                    ObjectiveStatus::Failed
+                }
+            }
+            Objective::SurviveWaves { waves } => {
+                if context.waves_cleared >= *waves {
+                    ObjectiveStatus::Complete
+                } else if context.progress.all_waves_cleared {
+                    ObjectiveStatus::Failed
+                } else {
+                    ObjectiveStatus::InProgress
+                }
+            }
+            Objective::KeepPlots { minimum } => {
+                if context.standing_plots < *minimum {
+                    ObjectiveStatus::Failed
+                } else {
+                    ObjectiveStatus::InProgress
+                }
+            }
+            Objective::HarvestCrops { crops, time_limit } => {
+                if context.progress.crops_harvested >= *crops {
+                    ObjectiveStatus::Complete
+                } else if context.progress.elapsed > *time_limit {
+                    ObjectiveStatus::Failed
+                } else {
+                    ObjectiveStatus::InProgress
+                }
+            }
+            Objective::KillBoss { troop_id } => {
+                if context.progress.bosses.contains(troop_id)
+                    && !context.living_bosses.contains(troop_id)
+                {
+                    ObjectiveStatus::Complete
+                } else if context.progress.all_waves_cleared
+                    && !context.progress.bosses.contains(troop_id)
+                {
+                    ObjectiveStatus::Failed
+                } else {
+                    ObjectiveStatus::InProgress
+                }
+            }
+        }
+    }
+
+    /// Checks the troops and waves this objective names exist. `directed` scenarios spawn their
+    /// own invaders, so any troop type may turn up as a boss.
+    pub fn check(
+        &self,
+        troop_types: &TroopTypes,
+        waves: &Waves,
+        directed: bool,
+    ) -> Result<(), String> {
+        match self {
+            Objective::ProtectType { troop_id } | Objective::KillBoss { troop_id }
+                if troop_types.get(*troop_id).is_none() =>
+            {
+                Err(format!("there is no troop type {}", troop_id))
+            }
+            Objective::KillBoss { troop_id } if !directed && !waves.spawns(*troop_id) => {
+                Err(format!("no wave spawns troop type {}", troop_id))
+            }
+            Objective::SurviveWaves { waves: count } if *count > waves.authored() => Err(format!(
+                "there are {} waves to survive, not {}",
+                waves.authored(),
+                count
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn describe(&self, context: &ObjectiveContext, troop_types: &TroopTypes) -> String {
+        let troop_name = |troop_id: &i32| {
+            troop_types
+                .get(*troop_id)
+                .map(|troop_type| troop_type.name)
+                .unwrap_or_else(|| format!("troop {}", troop_id))
+        };
+        match self {
+            Objective::ProtectType { troop_id } => format!("Protect your {}", troop_name(troop_id)),
+            Objective::SurviveWaves { waves } => format!(
+                "Survive {} waves ({}/{})",
+                waves,
+                context.waves_cleared.min(*waves),
+                waves
+            ),
+            Objective::KeepPlots { minimum } => format!(
+                "Keep {} plots standing ({} left)",
+                minimum, context.standing_plots
+            ),
+            Objective::HarvestCrops { crops, time_limit } => format!(
+                "Harvest {} crops in {}s ({}/{})",
+                crops,
+                (*time_limit - context.progress.elapsed).max(0.) as i32,
+                context.progress.crops_harvested.min(*crops),
+                crops
+            ),
+            Objective::KillBoss { troop_id } => format!("Slay the enemy {}", troop_name(troop_id)),
+        }
+    }
+}
+
+#[derive(Clone, Deserialize, TypeUuid)]
+#[uuid = "0d3f5b6c-5c64-4d8e-9a51-7f2f7f1e3c2a"]
+pub struct Scenario {
+    pub name: String,
+    pub description: String,
+    pub objectives: Vec<Objective>,
+    /// A script that drives the waves of this scenario, see the director module.
+    #[serde(default)]
+    pub director_path: Option<String>,
+    #[serde(skip_deserializing)]
+    pub director: Option<Handle<WasmScript>>,
+}
+
+#[derive(Default)]
+pub struct ScenarioAssetLoader;
+
+impl AssetLoader for ScenarioAssetLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut bevy::asset::LoadContext,
+    ) -> bevy::utils::BoxedFuture<'a, anyhow::Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let mut custom_asset = ron::de::from_bytes::<Scenario>(bytes)?;
+            if let Some(director_path) = custom_asset.director_path.clone() {
+                let wasm_bytes = compile_script(
+                    &director_path,
+                    load_context.read_asset_bytes(&director_path).await?,
+                )?;
+                validate_script(&director_path, ScriptRole::Director, &wasm_bytes)?;
+                custom_asset.director = Some(load_context.set_labeled_asset(
+                    "director_script",
+                    LoadedAsset::new(WasmScript::Loaded(
+                        format!("director:{}", custom_asset.name),
+                        wasm_bytes,
+                    )),
+                ));
+            }
+            load_context.set_default_asset(LoadedAsset::new(custom_asset));
+            Ok(())
+        })
+    }
+    fn extensions(&self) -> &[&str] {
+        &["scenario"]
+    }
+}
+
+#[derive(Resource)]
+pub struct Scenarios(pub HashMap<String, Scenario>);
+
+impl FromWorld for Scenarios {
+    fn from_world(world: &mut World) -> Self {
+        let scenarios = world.get_resource::<ScenarioAssets>().unwrap();
+        let assets = world.get_resource::<Assets<Scenario>>().unwrap();
+        let troop_types = world.get_resource::<TroopTypes>().unwrap();
+        let waves = world.get_resource::<Waves>().unwrap();
+        let mut map = HashMap::new();
+        scenarios.scenarios.iter().for_each(|scenario| {
+            if let Some(scenario) = assets.get(scenario) {
+                let directed = scenario.director.is_some();
+                match scenario
+                    .objectives
+                    .iter()
+                    .try_for_each(|objective| objective.check(troop_types, waves, directed))
+                {
+                    Ok(()) => {
+                        map.insert(scenario.name.clone(), scenario.clone());
+                    }
+                    Err(err) => warn!("Skipping scenario {}: {}", scenario.name, err),
+                }
+            }
+        });
+        Self(map)
+    }
+}
+
+impl Scenarios {
+    pub fn selected(&self) -> Option<Scenario> {
+        let name = std::env::var(SCENARIO_ENV).unwrap_or_else(|_| "default".to_string());
+        self.0.get(&name).cloned().or_else(|| {
+            warn!("No scenario named {}, using the default.", name);
+            self.0.get("default").cloned()
+        })
+    }
+}
+
+pub enum ScenarioOutcome {
+    Won(String),
+    Lost(String),
+}
+
+#[derive(Resource, Default)]
+pub struct ObjectiveProgress {
+    pub scenario: Option<Scenario>,
+    pub crops_harvested: usize,
+    /// Troop types whose boss has arrived and been tagged.
+    pub bosses: Vec<i32>,
+    /// Set once the last wave is cleared, leaving the objectives to decide what that means.
+    pub all_waves_cleared: bool,
+    pub elapsed: f32,
+    pub outcome: Option<ScenarioOutcome>,
+    pub descriptions: Vec<(String, ObjectiveStatus)>,
+}
+
+fn start_scenario_system(mut progress: ResMut<ObjectiveProgress>, scenarios: Res<Scenarios>) {
+    *progress = ObjectiveProgress {
+        scenario: scenarios.selected(),
+        ..Default::default()
+    };
+}
+
+fn objective_timer_system(
+    mut progress: ResMut<ObjectiveProgress>,
+    current_wave: Res<CurrentWave>,
+    clock: Res<BattleClock>,
+) {
+    if current_wave.wave.id >= 0 && progress.outcome.is_none() {
+        progress.elapsed += clock.delta_seconds();
+    }
+}
+
+/// Marks the enemy that a `KillBoss` objective is about.
+#[derive(Component)]
+pub struct Boss;
+
+fn boss_tagging_system(
+    mut commands: Commands,
+    mut progress: ResMut<ObjectiveProgress>,
+    arrivals: Query<(Entity, &Troop, &Faction), Added<Troop>>,
+) {
+    let boss_ids: Vec<i32> = progress
+        .scenario
+        .iter()
+        .flat_map(|scenario| scenario.objectives.iter())
+        .filter_map(|objective| match objective {
+            Objective::KillBoss { troop_id } => Some(*troop_id),
+            _ => None,
+        })
+        .collect();
+    for (entity, troop, faction) in arrivals.iter() {
+        let troop_id = troop.troop_type.id;
+        if faction.faction_id != Faction::player().faction_id
+            && boss_ids.contains(&troop_id)
+            && !progress.bosses.contains(&troop_id)
+        {
+            commands.entity(entity).insert(Boss);
+            progress.bosses.push(troop_id);
+        }
+    }
+}
+
+fn objective_system(
+    mut progress: ResMut<ObjectiveProgress>,
+    mut current_wave: ResMut<CurrentWave>,
+    mut crops_harvested: EventReader<CropHarvested>,
+    troops: Query<(&Troop, &Faction)>,
+    bosses: Query<&Troop, With<Boss>>,
+    plots: Query<&Structure, (With<HarvestSpot>, Without<Parent>)>,
+    troop_types: Res<TroopTypes>,
+) {
+    progress.crops_harvested += crops_harvested.iter().count();
+    if current_wave.wave.id < 0 || progress.outcome.is_some() {
+        // Between games, or already decided.
+        return;
+    }
+    let scenario = if let Some(scenario) = progress.scenario.clone() {
+        scenario
+    } else {
+        return;
+    };
+    let context = ObjectiveContext {
+        allied_troop_ids: troops
+            .iter()
+            .filter(|(_troop, faction)| faction.faction_id == Faction::player().faction_id)
+            .map(|(troop, _faction)| troop.troop_type.id)
+            .collect(),
+        standing_plots: plots
+            .iter()
+            .filter(|structure| !structure.is_destroyed())
+            .count(),
+        waves_cleared: current_wave.wave.id + progress.all_waves_cleared as i32,
+        living_bosses: bosses.iter().map(|troop| troop.troop_type.id).collect(),
+        progress: &progress,
+    };
+    let statuses: Vec<(String, ObjectiveStatus)> = scenario
+        .objectives
+        .iter()
+        .map(|objective| {
+            (
+                objective.describe(&context, &troop_types),
+                objective.status(&context),
+            )
+        })
+        .collect();
+    let failed = statuses
+        .iter()
+        .find(|(_description, status)| *status == ObjectiveStatus::Failed)
+        .map(|(description, _status)| description.clone());
+    let goals: Vec<ObjectiveStatus> = scenario
+        .objectives
+        .iter()
+        .zip(statuses.iter())
+        .filter(|(objective, _status)| objective.is_goal())
+        .map(|(_objective, (_description, status))| *status)
+        .collect();
+    // Without any goals, the scenario is won by clearing every wave.
+    let won = if goals.is_empty() {
+        progress.all_waves_cleared
+    } else {
+        goals
+            .iter()
+            .all(|status| *status == ObjectiveStatus::Complete)
+    };
+    progress.descriptions = statuses;
+    if let Some(failed) = failed {
+        progress.outcome = Some(ScenarioOutcome::Lost(failed));
+        current_wave.game_over();
+    } else if won {
+        progress.outcome = Some(ScenarioOutcome::Won(
+            "Every objective is complete!".to_string(),
+        ));
+        current_wave.victory();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context(progress: &ObjectiveProgress, waves_cleared: i32) -> ObjectiveContext {
+        ObjectiveContext {
+            allied_troop_ids: vec![],
+            standing_plots: 0,
+            waves_cleared,
+            living_bosses: vec![],
+            progress,
+        }
+    }
+
+    #[test]
+    fn unmet_goals_fail_once_the_waves_run_out() {
+        let survive = Objective::SurviveWaves { waves: 12 };
+        let boss = Objective::KillBoss { troop_id: 3 };
+        let mut progress = ObjectiveProgress::default();
+        assert!(survive.status(&context(&progress, 9)) == ObjectiveStatus::InProgress);
+        assert!(boss.status(&context(&progress, 9)) == ObjectiveStatus::InProgress);
+
+        progress.all_waves_cleared = true;
+        assert!(survive.status(&context(&progress, 10)) == ObjectiveStatus::Failed);
+        assert!(boss.status(&context(&progress, 10)) == ObjectiveStatus::Failed);
+
+        // A boss that did arrive can still be hunted down.
+        progress.bosses.push(3);
+        let mut living = context(&progress, 10);
+        living.living_bosses.push(3);
+        assert!(boss.status(&living) == ObjectiveStatus::InProgress);
+    }
+
+    #[test]
+    fn objectives_name_troops_and_waves_that_exist() {
+        let troop_types = TroopTypes(HashMap::default());
+        let waves = Waves::new([]);
+        let check = |objective: Objective| objective.check(&troop_types, &waves, false);
+
+        assert_eq!(
+            check(Objective::ProtectType { troop_id: 87 }),
+            Err("there is no troop type 87".to_string())
+        );
+        assert_eq!(
+            check(Objective::SurviveWaves { waves: 1 }),
+            Err("there are 0 waves to survive, not 1".to_string())
+        );
+        assert_eq!(check(Objective::KeepPlots { minimum: 4 }), Ok(()));
+    }
+}
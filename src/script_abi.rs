@@ -0,0 +1,544 @@
+use anyhow::anyhow;
+use wasmer::{FunctionType, Type};
+use wasmparser::{ExternalKind, ImportSectionEntryType, Parser, Payload, TypeDef};
+
+use crate::common_scripting::SCRIPT_MEMORY_PAGES;
+
+// How the guest types cross the boundary: `EntityId` is an f64, `Bool` an i8 widened to i32.
+const ENTITY: Type = Type::F64;
+const BOOL: Type = Type::I32;
+const I32: Type = Type::I32;
+const I64: Type = Type::I64;
+const F32: Type = Type::F32;
+const F64: Type = Type::F64;
+
+/// A function the game provides or calls: name, params and results.
+type Signature = (&'static str, &'static [Type], &'static [Type]);
+
+// What every script gets, directors included.
+const SHARED_IMPORTS: &[Signature] = &[
+    ("play_sound", &[I32], &[]),
+    ("get_random", &[], &[F32]),
+    ("log", &[I32, I32, I32], &[]),
+    ("get_stat_id", &[I32, I32], &[I32]),
+];
+
+// What scripts get on top of the shared imports when they run on an entity of their own.
+const COMMON_IMPORTS: &[Signature] = &[
+    ("despawn_entity", &[ENTITY], &[]),
+    ("attach_child", &[ENTITY, ENTITY], &[]),
+    ("spawn_harvestable_by_id", &[I32, BOOL], &[ENTITY]),
+    ("get_script_value", &[ENTITY, I32, F32], &[F32]),
+    ("set_script_value", &[ENTITY, I32, F32], &[]),
+    ("get_memory_i64", &[ENTITY, I32, I64], &[I64]),
+    ("set_memory_i64", &[ENTITY, I32, I64], &[]),
+    ("get_memory_f64", &[ENTITY, I32, F64], &[F64]),
+    ("set_memory_f64", &[ENTITY, I32, F64], &[]),
+    ("heal_troop", &[ENTITY, I32], &[]),
+];
+
+const BATTLE_IMPORTS: &[Signature] = &[
+    ("scan_enemies", &[ENTITY], &[]),
+    ("get_enemy_count", &[ENTITY], &[I32]),
+    ("get_enemy", &[ENTITY, I32], &[ENTITY]),
+    ("get_nearest_enemy", &[ENTITY], &[ENTITY]),
+    ("scan_allies", &[ENTITY], &[]),
+    ("get_ally_count", &[ENTITY], &[I32]),
+    ("get_ally", &[ENTITY, I32], &[ENTITY]),
+    ("get_health", &[ENTITY], &[I32]),
+    ("get_max_health", &[ENTITY], &[I32]),
+    ("get_troop_type_id", &[ENTITY], &[I32]),
+    ("get_faction", &[ENTITY], &[I32]),
+    ("get_cooldown_remaining", &[ENTITY], &[F32]),
+    ("get_attackers_of", &[ENTITY], &[I32]),
+    ("get_attacker_of", &[ENTITY, I32], &[ENTITY]),
+    ("get_x_of", &[ENTITY], &[F32]),
+    ("get_y_of", &[ENTITY], &[F32]),
+    ("get_distance", &[ENTITY, ENTITY], &[F32]),
+    ("get_move_speed", &[ENTITY], &[F32]),
+    ("get_attack_range", &[ENTITY], &[F32]),
+    ("retreat", &[ENTITY, F32], &[]),
+    ("move_towards", &[ENTITY, F32, F32, F32], &[]),
+    ("attack_enemy", &[ENTITY, ENTITY, I32], &[F32]),
+    ("scan_structures", &[ENTITY], &[]),
+    ("get_structure_count", &[ENTITY], &[I32]),
+    ("get_structure", &[ENTITY, I32], &[ENTITY]),
+    ("get_nearest_structure", &[ENTITY], &[ENTITY]),
+    ("get_structure_health", &[ENTITY], &[I32]),
+    ("attack_structure", &[ENTITY, ENTITY, I32], &[F32]),
+    ("define_attack", &[ENTITY, I32, I32], &[BOOL]),
+    ("get_attack_cooldown", &[I32], &[F32]),
+    ("set_attack_cooldown", &[ENTITY, I32, F32], &[BOOL]),
+    ("get_attack_damage", &[I32], &[I32]),
+    ("set_attack_damage", &[ENTITY, I32, I32], &[BOOL]),
+];
+
+const DELIVERY_IMPORTS: &[Signature] = &[
+    ("get_harvestable_id", &[ENTITY], &[I32]),
+    ("get_harvestable_value", &[ENTITY], &[I32]),
+    ("get_harvestable_is_real", &[ENTITY], &[BOOL]),
+    ("get_harvestable_is_plant", &[ENTITY], &[BOOL]),
+    ("get_harvestable_troop_id", &[ENTITY], &[I32]),
+    ("get_harvestable_tower_id", &[ENTITY], &[I32]),
+    ("get_harvest_spot_progress", &[ENTITY], &[F32]),
+    ("get_harvest_spot_progress_perc", &[ENTITY], &[F32]),
+    ("get_harvest_spot_harvest_time", &[ENTITY], &[F32]),
+    ("get_harvest_spot_harvestable", &[ENTITY], &[ENTITY]),
+    ("set_harvest_spot_progress", &[ENTITY, F32], &[]),
+    ("set_harvest_spot_progress_perc", &[ENTITY, F32], &[]),
+    ("set_harvest_spot_harvest_time", &[ENTITY, F32], &[]),
+    ("set_harvest_spot_harvestable", &[ENTITY, I32], &[]),
+    ("get_free_child_harvest_spot", &[ENTITY], &[ENTITY]),
+    ("set_visibility", &[ENTITY, BOOL], &[]),
+    ("stage_troop", &[ENTITY, I32], &[]),
+    ("can_build_tower", &[ENTITY], &[BOOL]),
+    ("build_tower", &[ENTITY, I32], &[]),
+];
+
+const DIRECTOR_IMPORTS: &[Signature] = &[
+    ("get_wave_id", &[], &[I32]),
+    ("get_army_size", &[], &[I32]),
+    ("get_invader_count", &[], &[I32]),
+    ("set_spawn_buff", &[I32, F32], &[]),
+    ("spawn_invader", &[I32, I32], &[BOOL]),
+    ("end_wave", &[], &[]),
+];
+
+const TROOP_EXPORTS: &[Signature] = &[
+    ("battle_action", &[ENTITY], &[F32]),
+    ("on_death", &[ENTITY], &[BOOL]),
+    ("on_spawn", &[ENTITY], &[]),
+    ("on_damaged", &[ENTITY, ENTITY, I32], &[]),
+    ("on_kill", &[ENTITY, ENTITY], &[]),
+    ("on_wave_start", &[ENTITY, I32], &[]),
+    ("king_ability", &[ENTITY, I32], &[BOOL]),
+];
+
+const DELIVERY_EXPORTS: &[Signature] = &[
+    ("can_produce", &[ENTITY], &[BOOL]),
+    ("produce", &[ENTITY], &[ENTITY]),
+    ("can_receive", &[ENTITY, ENTITY], &[BOOL]),
+    ("receive", &[ENTITY, ENTITY, ENTITY], &[]),
+    ("rejected", &[ENTITY, ENTITY], &[]),
+];
+
+const DIRECTOR_EXPORTS: &[Signature] = &[("director_tick", &[F32], &[])];
+
+/// What a script is loaded as, which decides the imports it gets and the exports it must have.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScriptRole {
+    Troop,
+    Delivery,
+    Director,
+}
+
+impl ScriptRole {
+    fn imports(self) -> impl Iterator<Item = &'static Signature> {
+        let (common_imports, role_imports) = match self {
+            ScriptRole::Troop => (COMMON_IMPORTS, BATTLE_IMPORTS),
+            ScriptRole::Delivery => (COMMON_IMPORTS, DELIVERY_IMPORTS),
+            ScriptRole::Director => (&[][..], DIRECTOR_IMPORTS),
+        };
+        SHARED_IMPORTS
+            .iter()
+            .chain(common_imports)
+            .chain(role_imports)
+    }
+
+    fn exports(self) -> &'static [Signature] {
+        match self {
+            ScriptRole::Troop => TROOP_EXPORTS,
+            ScriptRole::Delivery => DELIVERY_EXPORTS,
+            ScriptRole::Director => DIRECTOR_EXPORTS,
+        }
+    }
+
+    /// Exports that are called together; a script needs every export of at least one group.
+    fn required_exports(self) -> &'static [&'static [&'static str]] {
+        match self {
+            ScriptRole::Troop => &[&["battle_action", "on_death"]],
+            ScriptRole::Delivery => &[&["can_produce", "produce"], &["can_receive", "receive"]],
+            ScriptRole::Director => &[&["director_tick"]],
+        }
+    }
+}
+
+fn describe(params: &[Type], results: &[Type]) -> String {
+    let list = |types: &[Type]| {
+        types
+            .iter()
+            .map(|ty| format!("{:?}", ty).to_lowercase())
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    format!("({}) -> ({})", list(params), list(results))
+}
+
+fn check_signature(kind: &str, (name, params, results): &Signature, item: &Item) -> Option<String> {
+    match item {
+        Item::Function(function) if matches(function, params, results) => None,
+        Item::Function(function) => Some(format!(
+            "{} `{}` should be {}, found {}",
+            kind,
+            name,
+            describe(params, results),
+            describe(function.params(), function.results())
+        )),
+        _ => Some(format!("{} `{}` should be a function", kind, name)),
+    }
+}
+
+fn matches(function: &FunctionType, params: &[Type], results: &[Type]) -> bool {
+    function.params() == params && function.results() == results
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, b_char) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = if a_char == *b_char {
+                diagonal
+            } else {
+                1 + diagonal.min(above).min(row[j])
+            };
+            diagonal = above;
+        }
+    }
+    row[b.len()]
+}
+
+fn closest<'a>(name: &str, known: impl Iterator<Item = &'a Signature>) -> Option<&'static str> {
+    known
+        .map(|(known, _, _)| (edit_distance(name, known), *known))
+        .filter(|(distance, _)| *distance <= 3)
+        .min()
+        .map(|(_, known)| known)
+}
+
+/// An import or export, as far as checking a script needs to know.
+enum Item {
+    Function(FunctionType),
+    Memory { minimum: u64 },
+    Other,
+}
+
+struct Interface {
+    imports: Vec<(String, String, Item)>,
+    exports: Vec<(String, Item)>,
+}
+
+fn value_type(ty: wasmparser::Type) -> Type {
+    match ty {
+        wasmparser::Type::I32 => Type::I32,
+        wasmparser::Type::I64 => Type::I64,
+        wasmparser::Type::F32 => Type::F32,
+        wasmparser::Type::F64 => Type::F64,
+        wasmparser::Type::V128 => Type::V128,
+        wasmparser::Type::ExternRef => Type::ExternRef,
+        _ => Type::FuncRef,
+    }
+}
+
+fn function_item(types: &[Option<FunctionType>], index: u32) -> Item {
+    types
+        .get(index as usize)
+        .cloned()
+        .flatten()
+        .map_or(Item::Other, Item::Function)
+}
+
+// Reads the sections directly, so checking a script doesn't compile it a second time on top of the
+// plugin's own compile.
+fn read_interface(wasm_bytes: &[u8]) -> anyhow::Result<Interface> {
+    let mut types = Vec::new();
+    // The type of every function, imported ones first, as export indices count them.
+    let mut functions = Vec::new();
+    // Likewise the minimum size of every memory.
+    let mut memories = Vec::new();
+    let mut interface = Interface {
+        imports: Vec::new(),
+        exports: Vec::new(),
+    };
+    for payload in Parser::new(0).parse_all(wasm_bytes) {
+        match payload? {
+            Payload::TypeSection(reader) => {
+                for ty in reader {
+                    types.push(match ty? {
+                        TypeDef::Func(function) => Some(FunctionType::new(
+                            function
+                                .params
+                                .iter()
+                                .copied()
+                                .map(value_type)
+                                .collect::<Vec<_>>(),
+                            function
+                                .returns
+                                .iter()
+                                .copied()
+                                .map(value_type)
+                                .collect::<Vec<_>>(),
+                        )),
+                        _ => None,
+                    });
+                }
+            }
+            Payload::ImportSection(reader) => {
+                for import in reader {
+                    let import = import?;
+                    let item = match import.ty {
+                        ImportSectionEntryType::Function(ty) => {
+                            functions.push(ty);
+                            function_item(&types, ty)
+                        }
+                        ImportSectionEntryType::Memory(memory) => {
+                            memories.push(memory.initial);
+                            Item::Memory {
+                                minimum: memory.initial,
+                            }
+                        }
+                        _ => Item::Other,
+                    };
+                    interface.imports.push((
+                        import.module.to_string(),
+                        import.field.unwrap_or_default().to_string(),
+                        item,
+                    ));
+                }
+            }
+            Payload::FunctionSection(reader) => {
+                for ty in reader {
+                    functions.push(ty?);
+                }
+            }
+            Payload::MemorySection(reader) => {
+                for memory in reader {
+                    memories.push(memory?.initial);
+                }
+            }
+            Payload::ExportSection(reader) => {
+                for export in reader {
+                    let export = export?;
+                    let item = match export.kind {
+                        ExternalKind::Function => functions
+                            .get(export.index as usize)
+                            .map_or(Item::Other, |ty| function_item(&types, *ty)),
+                        ExternalKind::Memory => memories
+                            .get(export.index as usize)
+                            .map_or(Item::Other, |minimum| Item::Memory { minimum: *minimum }),
+                        _ => Item::Other,
+                    };
+                    interface.exports.push((export.field.to_string(), item));
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(interface)
+}
+
+/// Checks a script's imports and exports against what the game provides and calls for `role`,
+/// and returns the names of its exports. Every problem is listed in the error, so a broken mod
+/// fails to load once instead of failing each time it's called.
+pub fn validate_script(
+    name: &str,
+    role: ScriptRole,
+    wasm_bytes: &[u8],
+) -> anyhow::Result<Vec<String>> {
+    let interface = read_interface(wasm_bytes)
+        .map_err(|err| anyhow!("{} is not a wasm module: {}", name, err))?;
+    let mut problems = Vec::new();
+
+    for (module, import, item) in &interface.imports {
+        if module != "env" {
+            problems.push(format!(
+                "imports `{}` from `{}`, but everything is provided by `env`",
+                import, module
+            ));
+            continue;
+        }
+        if import == "memory" {
+            match item {
+                Item::Memory { minimum } if *minimum > SCRIPT_MEMORY_PAGES as u64 => {
+                    problems.push(format!(
+                        "needs {} pages of memory, but scripts get {}",
+                        minimum, SCRIPT_MEMORY_PAGES
+                    ));
+                }
+                Item::Memory { .. } => {}
+                _ => problems.push("import `memory` should be a memory".to_string()),
+            }
+            continue;
+        }
+        match role
+            .imports()
+            .find(|(known, _, _)| *known == import.as_str())
+        {
+            Some(signature) => problems.extend(check_signature("import", signature, item)),
+            None => problems.push(match closest(import, role.imports()) {
+                Some(suggestion) => format!(
+                    "unknown import `{}`, did you mean `{}`?",
+                    import, suggestion
+                ),
+                None => format!("unknown import `{}` for a {:?} script", import, role),
+            }),
+        }
+    }
+
+    // Text is read from `env.memory`, so a script with its own memory would hand over pointers
+    // into the wrong one.
+    let imports_memory = interface
+        .imports
+        .iter()
+        .any(|(module, import, _)| module == "env" && import == "memory");
+    for (_, import, _) in &interface.imports {
+        if !imports_memory && (import == "log" || import == "get_stat_id") {
+            problems.push(format!(
+                "imports `{}` without importing `env.memory`, link with `--import-memory`",
+                import
+            ));
+        }
+    }
+    let exports = &interface.exports;
+    if exports
+        .iter()
+        .any(|(_, item)| matches!(item, Item::Memory { .. }))
+    {
+        problems.push(
+            "exports its own memory instead of importing `env.memory`, link with `--import-memory`"
+                .to_string(),
+        );
+    }
+    for signature in role.exports() {
+        if let Some((_, item)) = exports.iter().find(|(export, _)| export == signature.0) {
+            problems.extend(check_signature("export", signature, item));
+        }
+    }
+    let exported = |name: &&str| exports.iter().any(|(export, _)| export == name);
+    let groups = role.required_exports();
+    if !groups.iter().any(|group| group.iter().all(exported)) {
+        let missing: Vec<_> = groups
+            .iter()
+            .map(|group| {
+                group
+                    .iter()
+                    .filter(|name| !exported(*name))
+                    .map(|name| format!("`{}`", name))
+                    .collect::<Vec<_>>()
+                    .join(" and ")
+            })
+            .collect();
+        problems.push(format!("missing export {}", missing.join(", or ")));
+    }
+
+    if problems.is_empty() {
+        Ok(interface
+            .exports
+            .into_iter()
+            .map(|(export, _)| export)
+            .collect())
+    } else {
+        Err(anyhow!(
+            "{} is not a valid {:?} script: {}",
+            name,
+            role,
+            problems.join("; ")
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common_scripting::compile_script;
+
+    fn validation_error(role: ScriptRole, wat: &str) -> String {
+        let wasm_bytes = compile_script("test.wat", wat.as_bytes().to_vec()).unwrap();
+        validate_script("test.wat", role, &wasm_bytes)
+            .unwrap_err()
+            .to_string()
+    }
+
+    #[test]
+    fn validation_suggests_the_import_that_was_meant() {
+        let err = validation_error(
+            ScriptRole::Troop,
+            r#"(module
+                (import "env" "get_neares_enemy" (func (param f64) (result f64)))
+                (func (export "battle_action") (param f64) (result f32) f32.const 1)
+                (func (export "on_death") (param f64) (result i32) i32.const 1))"#,
+        );
+        assert!(
+            err.contains("unknown import `get_neares_enemy`, did you mean `get_nearest_enemy`?"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn validation_checks_export_signatures() {
+        let err = validation_error(
+            ScriptRole::Troop,
+            r#"(module
+                (func (export "battle_action") (param i32) (result f32) f32.const 1))"#,
+        );
+        assert!(
+            err.contains("export `battle_action` should be (f64) -> (f32), found (i32) -> (f32)"),
+            "{}",
+            err
+        );
+        assert!(err.contains("missing export `on_death`"), "{}", err);
+    }
+
+    #[test]
+    fn delivery_scripts_need_a_whole_role() {
+        let err = validation_error(
+            ScriptRole::Delivery,
+            r#"(module
+                (func (export "can_receive") (param f64 f64) (result i32) i32.const 1))"#,
+        );
+        assert!(
+            err.contains("missing export `can_produce` and `produce`, or `receive`"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn scripts_that_pass_text_must_import_their_memory() {
+        let err = validation_error(
+            ScriptRole::Troop,
+            r#"(module
+                (import "env" "log" (func (param i32 i32 i32)))
+                (memory (export "memory") 1)
+                (func (export "battle_action") (param f64) (result f32) f32.const 1)
+                (func (export "on_death") (param f64) (result i32) i32.const 1))"#,
+        );
+        assert!(
+            err.contains("imports `log` without importing `env.memory`"),
+            "{}",
+            err
+        );
+        assert!(err.contains("exports its own memory"), "{}", err);
+    }
+
+    #[test]
+    fn directors_only_get_director_imports() {
+        let err = validation_error(
+            ScriptRole::Director,
+            r#"(module
+                (import "env" "get_wave_id" (func (result i32)))
+                (import "env" "get_nearest_enemy" (func (param f64) (result f64)))
+                (func (export "director_tick") (param f32)))"#,
+        );
+        assert!(
+            err.contains("unknown import `get_nearest_enemy` for a Director script"),
+            "{}",
+            err
+        );
+        assert!(!err.contains("get_wave_id"), "{}", err);
+    }
+}
@@ -0,0 +1,167 @@
+use std::fmt::Display;
+
+use bevy::{ecs::system::Command, prelude::*};
+use bevy_wasm_scripting::WasmScript;
+
+use crate::{loading::FontAssets, GameState};
+
+pub const OVERLAY_KEY: KeyCode = KeyCode::F3;
+
+pub struct ScriptErrorPlugin;
+
+impl Plugin for ScriptErrorPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ScriptError>()
+            .init_resource::<ScriptErrors>()
+            .add_system_set(
+                SystemSet::on_enter(GameState::Playing).with_system(setup_script_error_overlay),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(script_error_system)
+                    .with_system(script_error_overlay_system),
+            );
+    }
+}
+
+/// A failed call into a script. Send one wherever a call can fail, and it shows up in the overlay.
+pub struct ScriptError {
+    pub script: Handle<WasmScript>,
+    pub export: &'static str,
+    pub error: String,
+}
+
+impl ScriptError {
+    pub fn new(script: &Handle<WasmScript>, export: &'static str, error: impl Display) -> Self {
+        Self {
+            script: script.clone(),
+            export,
+            error: error.to_string(),
+        }
+    }
+}
+
+// Lets systems that only have Commands at hand report errors too.
+impl Command for ScriptError {
+    fn write(self, world: &mut World) {
+        world.send_event(self);
+    }
+}
+
+pub struct ScriptErrorEntry {
+    pub script: String,
+    pub export: &'static str,
+    pub error: String,
+    pub first_seen: f64,
+    pub count: usize,
+}
+
+#[derive(Resource)]
+pub struct ScriptErrors {
+    pub entries: Vec<ScriptErrorEntry>,
+    pub visible: bool,
+}
+
+impl Default for ScriptErrors {
+    fn default() -> Self {
+        Self {
+            entries: Vec::new(),
+            visible: true,
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct ScriptErrorText;
+
+fn setup_script_error_overlay(mut commands: Commands, fonts: Res<FontAssets>) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font: fonts.fira_sans.clone(),
+                font_size: 14.,
+                color: Color::rgb(1., 0.4, 0.4),
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                top: Val::Px(48.),
+                left: Val::Px(8.),
+                ..Default::default()
+            },
+            max_size: Size::new(Val::Px(640.), Val::Undefined),
+            ..Default::default()
+        }),
+        ScriptErrorText,
+    ));
+}
+
+// Scripts loaded by a troop carry their label, the rest are named by their path.
+pub fn script_name(
+    script: &Handle<WasmScript>,
+    scripts: &Assets<WasmScript>,
+    asset_server: &AssetServer,
+) -> String {
+    if let Some(WasmScript::Loaded(name, _bytes)) = scripts.get(script) {
+        name.clone()
+    } else if let Some(path) = asset_server.get_handle_path(script) {
+        path.path().display().to_string()
+    } else {
+        "unknown script".to_string()
+    }
+}
+
+fn script_error_system(
+    mut errors: ResMut<ScriptErrors>,
+    mut error_events: EventReader<ScriptError>,
+    scripts: Res<Assets<WasmScript>>,
+    asset_server: Res<AssetServer>,
+    time: Res<Time>,
+) {
+    for event in error_events.iter() {
+        let script = script_name(&event.script, &scripts, &asset_server);
+        if let Some(entry) = errors
+            .entries
+            .iter_mut()
+            .find(|entry| entry.script == script && entry.export == event.export)
+        {
+            entry.count += 1;
+            entry.error = event.error.clone();
+        } else {
+            // Only the first failure is logged, the overlay keeps count of the rest.
+            error!("Error in {} of {}: {}", event.export, script, event.error);
+            errors.entries.push(ScriptErrorEntry {
+                script,
+                export: event.export,
+                error: event.error.clone(),
+                first_seen: time.elapsed_seconds_f64(),
+                count: 1,
+            });
+        }
+    }
+}
+
+fn script_error_overlay_system(
+    mut errors: ResMut<ScriptErrors>,
+    input: Res<Input<KeyCode>>,
+    mut overlay: Query<(&mut Text, &mut Visibility), With<ScriptErrorText>>,
+) {
+    if input.just_pressed(OVERLAY_KEY) {
+        errors.visible = !errors.visible;
+    }
+    for (mut text, mut visibility) in overlay.iter_mut() {
+        visibility.is_visible = errors.visible && !errors.entries.is_empty();
+        text.sections[0].value = errors
+            .entries
+            .iter()
+            .map(|entry| {
+                format!(
+                    "{} {} x{} (since {:.1}s): {}\n",
+                    entry.script, entry.export, entry.count, entry.first_seen, entry.error
+                )
+            })
+            .collect();
+    }
+}
@@ -0,0 +1,194 @@
+//! A per-call budget of wasm operations, so a script stuck in a loop traps instead of freezing
+//! the game. The trap goes through the same quarantine as any other, leaving the troop idle.
+
+use bevy::prelude::*;
+
+/// Set this environment variable to change how many operations one call into a script may run.
+pub const SCRIPT_FUEL_ENV: &str = "HARVESTS_SCRIPT_FUEL";
+// Far more than any shipped script needs in a call, and still well under a frame.
+pub const DEFAULT_SCRIPT_FUEL: u64 = 5_000_000;
+
+pub struct ScriptFuelPlugin;
+
+impl Plugin for ScriptFuelPlugin {
+    // Goes after `WasmPlugin`, whose store it replaces before any script is compiled. The browser
+    // build runs scripts on the browser's own engine, which can't be metered.
+    fn build(&self, app: &mut App) {
+        let fuel = ScriptFuel::from_env();
+        #[cfg(feature = "non-js")]
+        app.insert_resource(bevy_wasm_scripting::WasmerStore(metered::store(
+            fuel.budget,
+        )));
+        app.insert_resource(fuel);
+    }
+}
+
+#[derive(Resource)]
+pub struct ScriptFuel {
+    pub budget: u64,
+}
+
+impl ScriptFuel {
+    pub fn from_env() -> Self {
+        let budget = match std::env::var(SCRIPT_FUEL_ENV) {
+            Ok(budget) => budget.parse().unwrap_or_else(|err| {
+                warn!("Ignoring {}={}: {}", SCRIPT_FUEL_ENV, budget, err);
+                DEFAULT_SCRIPT_FUEL
+            }),
+            Err(_) => DEFAULT_SCRIPT_FUEL,
+        };
+        Self { budget }
+    }
+}
+
+// Counts operations like wasmer_middlewares' `Metering`, which can't serve here: one of those
+// refuses a second module, while every script shares the store's engine, and refilling it takes
+// the `Instance`, which bevy_wasm_scripting keeps to itself. So the budget lives in a global of
+// each module and every export refills it on entry, which makes it per call.
+#[cfg(feature = "non-js")]
+mod metered {
+    use std::sync::{Arc, Mutex};
+
+    use wasmer::{
+        wasmparser::{Operator, Type as WpType, TypeOrFuncType},
+        CompilerConfig, Cranelift, EngineBuilder, ExportIndex, FunctionMiddleware, GlobalInit,
+        GlobalType, LocalFunctionIndex, MiddlewareError, MiddlewareReaderState, ModuleMiddleware,
+        Mutability, Store, Type,
+    };
+    use wasmer_types::ModuleInfo;
+
+    pub fn store(budget: u64) -> Store {
+        let mut compiler = Cranelift::default();
+        compiler.push_middleware(Arc::new(Fuel {
+            budget,
+            module: Mutex::default(),
+        }));
+        Store::new(EngineBuilder::new(compiler))
+    }
+
+    #[derive(Debug, Default)]
+    struct FuelModule {
+        remaining: u32,
+        exports: Vec<LocalFunctionIndex>,
+    }
+
+    // Modules are compiled one at a time through the store, so the module being compiled is the
+    // one `transform_module_info` saw last.
+    #[derive(Debug)]
+    struct Fuel {
+        budget: u64,
+        module: Mutex<FuelModule>,
+    }
+
+    impl ModuleMiddleware for Fuel {
+        fn generate_function_middleware(
+            &self,
+            local_function_index: LocalFunctionIndex,
+        ) -> Box<dyn FunctionMiddleware> {
+            let module = self.module.lock().unwrap();
+            Box::new(FunctionFuel {
+                budget: self.budget,
+                remaining: module.remaining,
+                refill: module.exports.contains(&local_function_index),
+                started: false,
+                cost: 0,
+            })
+        }
+
+        fn transform_module_info(
+            &self,
+            module_info: &mut ModuleInfo,
+        ) -> Result<(), MiddlewareError> {
+            let remaining = module_info
+                .globals
+                .push(GlobalType::new(Type::I64, Mutability::Var));
+            module_info
+                .global_initializers
+                .push(GlobalInit::I64Const(self.budget as i64));
+            let exports = module_info
+                .exports
+                .values()
+                .filter_map(|export| match export {
+                    ExportIndex::Function(function) => module_info.local_func_index(*function),
+                    _ => None,
+                })
+                .collect();
+            *self.module.lock().unwrap() = FuelModule {
+                remaining: remaining.as_u32(),
+                exports,
+            };
+            Ok(())
+        }
+    }
+
+    #[derive(Debug)]
+    struct FunctionFuel {
+        budget: u64,
+        remaining: u32,
+        refill: bool,
+        started: bool,
+        // Operations since the last charge, which is made where control can leave a block.
+        cost: u64,
+    }
+
+    impl FunctionMiddleware for FunctionFuel {
+        fn feed<'a>(
+            &mut self,
+            operator: Operator<'a>,
+            state: &mut MiddlewareReaderState<'a>,
+        ) -> Result<(), MiddlewareError> {
+            let remaining = self.remaining;
+            if !self.started {
+                self.started = true;
+                if self.refill {
+                    state.extend(&[
+                        Operator::I64Const {
+                            value: self.budget as i64,
+                        },
+                        Operator::GlobalSet {
+                            global_index: remaining,
+                        },
+                    ]);
+                }
+            }
+            self.cost += 1;
+            match operator {
+                Operator::Loop { .. }
+                | Operator::End
+                | Operator::Else
+                | Operator::Br { .. }
+                | Operator::BrTable { .. }
+                | Operator::BrIf { .. }
+                | Operator::Call { .. }
+                | Operator::CallIndirect { .. }
+                | Operator::Return => {
+                    let cost = self.cost as i64;
+                    state.extend(&[
+                        Operator::GlobalGet {
+                            global_index: remaining,
+                        },
+                        Operator::I64Const { value: cost },
+                        Operator::I64LtU,
+                        Operator::If {
+                            ty: TypeOrFuncType::Type(WpType::EmptyBlockType),
+                        },
+                        Operator::Unreachable,
+                        Operator::End,
+                        Operator::GlobalGet {
+                            global_index: remaining,
+                        },
+                        Operator::I64Const { value: cost },
+                        Operator::I64Sub,
+                        Operator::GlobalSet {
+                            global_index: remaining,
+                        },
+                    ]);
+                    self.cost = 0;
+                }
+                _ => {}
+            }
+            state.push_operator(operator);
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,228 @@
+use std::fmt::Write as _;
+
+use bevy::{
+    prelude::*,
+    utils::{Duration, HashMap, Instant},
+};
+use bevy_wasm_scripting::WasmScript;
+
+use crate::{loading::FontAssets, script_errors::script_name, GameState};
+
+/// Set this environment variable to a CSV path to time every script call. The timings are shown
+/// with `PROFILE_KEY` and written to the path with `PROFILE_DUMP_KEY`. Without it there is no
+/// `ScriptProfile` resource at all.
+pub const SCRIPT_PROFILE_ENV: &str = "HARVESTS_SCRIPT_PROFILE";
+pub const PROFILE_KEY: KeyCode = KeyCode::F4;
+pub const PROFILE_DUMP_KEY: KeyCode = KeyCode::F5;
+
+// The panel only has room for the most expensive ones.
+const PANEL_ROWS: usize = 16;
+
+pub struct ScriptProfilerPlugin;
+
+impl Plugin for ScriptProfilerPlugin {
+    fn build(&self, app: &mut App) {
+        if let Some(profile) = ScriptProfile::from_env() {
+            app.insert_resource(profile);
+        }
+        app.add_system_set(
+            SystemSet::on_enter(GameState::Playing).with_system(setup_script_profile_panel),
+        )
+        .add_system_set(
+            SystemSet::on_update(GameState::Playing).with_system(script_profile_panel_system),
+        );
+    }
+}
+
+#[derive(Default)]
+pub struct ScriptTiming {
+    pub calls: u64,
+    pub total: Duration,
+    pub max: Duration,
+}
+
+impl ScriptTiming {
+    fn add(&mut self, other: &ScriptTiming) {
+        self.calls += other.calls;
+        self.total += other.total;
+        self.max = self.max.max(other.max);
+    }
+}
+
+#[derive(Resource)]
+pub struct ScriptProfile {
+    csv_path: String,
+    pub timings: HashMap<(Handle<WasmScript>, &'static str), ScriptTiming>,
+    pub visible: bool,
+}
+
+impl ScriptProfile {
+    pub fn from_env() -> Option<Self> {
+        let path = std::env::var(SCRIPT_PROFILE_ENV).ok()?;
+        info!("Profiling scripts, {:?} writes {}", PROFILE_DUMP_KEY, path);
+        Some(Self {
+            csv_path: path,
+            timings: HashMap::default(),
+            visible: true,
+        })
+    }
+
+    // Hot reloading moves a script to a new handle, so rows are merged by name, costliest first.
+    fn rows(
+        &self,
+        scripts: &Assets<WasmScript>,
+        asset_server: &AssetServer,
+    ) -> Vec<(String, &'static str, ScriptTiming)> {
+        let mut rows: Vec<(String, &'static str, ScriptTiming)> = Vec::new();
+        for ((script, export), timing) in self.timings.iter() {
+            let name = script_name(script, scripts, asset_server);
+            match rows
+                .iter_mut()
+                .find(|(row_name, row_export, _)| *row_name == name && *row_export == *export)
+            {
+                Some((_, _, row)) => row.add(timing),
+                None => {
+                    let mut row = ScriptTiming::default();
+                    row.add(timing);
+                    rows.push((name, *export, row));
+                }
+            }
+        }
+        rows.sort_by(|(_, _, a), (_, _, b)| b.total.cmp(&a.total));
+        rows
+    }
+}
+
+/// Runs `call`, timing it against `export` of `script` when profiling.
+pub fn profiled<T>(
+    profile: &mut Option<ResMut<ScriptProfile>>,
+    script: &Handle<WasmScript>,
+    export: &'static str,
+    call: impl FnOnce() -> T,
+) -> T {
+    let profile = match profile {
+        Some(profile) => profile,
+        None => return call(),
+    };
+    let start = Instant::now();
+    let result = call();
+    let elapsed = start.elapsed();
+    let timing = profile
+        .timings
+        .entry((script.clone_weak(), export))
+        .or_default();
+    timing.calls += 1;
+    timing.total += elapsed;
+    timing.max = timing.max.max(elapsed);
+    result
+}
+
+fn micros(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1_000_000.
+}
+
+fn mean_micros(timing: &ScriptTiming) -> f64 {
+    micros(timing.total) / timing.calls.max(1) as f64
+}
+
+fn profile_csv(rows: &[(String, &'static str, ScriptTiming)]) -> String {
+    let mut csv = String::from("script,export,calls,total_us,mean_us,max_us\n");
+    for (script, export, timing) in rows {
+        let _ = writeln!(
+            csv,
+            "{},{},{},{:.0},{:.1},{:.0}",
+            script,
+            export,
+            timing.calls,
+            micros(timing.total),
+            mean_micros(timing),
+            micros(timing.max)
+        );
+    }
+    csv
+}
+
+#[derive(Component)]
+pub struct ScriptProfileText;
+
+fn setup_script_profile_panel(
+    mut commands: Commands,
+    fonts: Res<FontAssets>,
+    profile: Option<Res<ScriptProfile>>,
+) {
+    if profile.is_none() {
+        return;
+    }
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font: fonts.fira_sans.clone(),
+                font_size: 14.,
+                color: Color::rgb(0.6, 0.9, 1.),
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                top: Val::Px(48.),
+                right: Val::Px(8.),
+                ..Default::default()
+            },
+            max_size: Size::new(Val::Px(560.), Val::Undefined),
+            ..Default::default()
+        }),
+        ScriptProfileText,
+    ));
+}
+
+fn script_profile_panel_system(
+    profile: Option<ResMut<ScriptProfile>>,
+    input: Res<Input<KeyCode>>,
+    scripts: Res<Assets<WasmScript>>,
+    asset_server: Res<AssetServer>,
+    mut panel: Query<(&mut Text, &mut Visibility), With<ScriptProfileText>>,
+) {
+    let mut profile = match profile {
+        Some(profile) => profile,
+        None => return,
+    };
+    if input.just_pressed(PROFILE_KEY) {
+        profile.visible = !profile.visible;
+    }
+    let dump = input.just_pressed(PROFILE_DUMP_KEY);
+    // Merging and sorting every frame is wasted while nobody looks at the rows.
+    let rows = if profile.visible || dump {
+        profile.rows(&scripts, &asset_server)
+    } else {
+        Vec::new()
+    };
+    if dump {
+        let path = &profile.csv_path;
+        match std::fs::write(path, profile_csv(&rows)) {
+            Ok(()) => info!("Wrote script profile to {}", path),
+            Err(err) => error!("Could not write script profile {}: {}", path, err),
+        }
+    }
+    for (mut text, mut visibility) in panel.iter_mut() {
+        visibility.is_visible = profile.visible && !rows.is_empty();
+        if !visibility.is_visible {
+            continue;
+        }
+        text.sections[0].value = rows
+            .iter()
+            .take(PANEL_ROWS)
+            .map(|(script, export, timing)| {
+                format!(
+                    "{} {} x{}: {:.1}ms total, {:.0}us mean, {:.0}us max\n",
+                    script,
+                    export,
+                    timing.calls,
+                    micros(timing.total) / 1000.,
+                    mean_micros(timing),
+                    micros(timing.max)
+                )
+            })
+            .collect();
+    }
+}
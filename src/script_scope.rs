@@ -0,0 +1,182 @@
+use std::cell::RefCell;
+
+use bevy::prelude::*;
+use wasmer::RuntimeError;
+
+use crate::battle::Troop;
+
+/// The capability set an export runs with. Imports that change the world check it, and trap when
+/// a script reaches past it, so a mod script can only change what it was handed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScriptCategory {
+    /// Battle exports change only their own troop, and reach others through attacks.
+    Battle,
+    /// King abilities may also buff, heal and slow any other troop.
+    Ability,
+    /// Delivery exports change the source or dropoff, its child spots and the delivered entity,
+    /// and may spawn and despawn harvestables.
+    Delivery,
+}
+
+impl ScriptCategory {
+    fn may_change(self, world: &World, entities: &[Entity], entity: Entity) -> bool {
+        entities.contains(&entity)
+            || match self {
+                ScriptCategory::Battle => false,
+                ScriptCategory::Ability => world.get::<Troop>(entity).is_some(),
+                ScriptCategory::Delivery => world
+                    .get::<Parent>(entity)
+                    .map_or(false, |parent| entities.contains(&parent.get())),
+            }
+    }
+
+    fn may_spawn(self) -> bool {
+        self == ScriptCategory::Delivery
+    }
+}
+
+struct ScriptScope {
+    category: ScriptCategory,
+    entities: Vec<Entity>,
+}
+
+// Exports run synchronously on the thread of the system calling them, so the scope only has to
+// last for the call.
+thread_local! {
+    static SCRIPT_SCOPE: RefCell<Option<ScriptScope>> = RefCell::new(None);
+}
+
+/// Runs `call` with the imports limited to `category`, over `entities`.
+pub fn with_scope<T>(category: ScriptCategory, entities: &[Entity], call: impl FnOnce() -> T) -> T {
+    let scope = ScriptScope {
+        category,
+        entities: entities.to_vec(),
+    };
+    let previous = SCRIPT_SCOPE.with(|current| current.replace(Some(scope)));
+    let _restore = RestoreScope(previous);
+    call()
+}
+
+// Puts the outer scope back when the call returns or unwinds, so a panic can't leave an export's
+// scope behind for whatever runs next on the thread.
+struct RestoreScope(Option<ScriptScope>);
+
+impl Drop for RestoreScope {
+    fn drop(&mut self) {
+        let previous = self.0.take();
+        SCRIPT_SCOPE.with(|current| *current.borrow_mut() = previous);
+    }
+}
+
+fn check(
+    import: &'static str,
+    f: impl FnOnce(&ScriptScope) -> Option<String>,
+) -> Result<(), RuntimeError> {
+    SCRIPT_SCOPE.with(|current| match &*current.borrow() {
+        Some(scope) => match f(scope) {
+            Some(reason) => Err(RuntimeError::new(format!("{} {}", import, reason))),
+            None => Ok(()),
+        },
+        None => Err(RuntimeError::new(format!(
+            "{} called outside of a script export",
+            import
+        ))),
+    })
+}
+
+/// Traps unless the running export may change `entity`.
+pub fn check_entity(
+    world: &World,
+    import: &'static str,
+    entity: Entity,
+) -> Result<(), RuntimeError> {
+    check(import, |scope| {
+        if scope.category.may_change(world, &scope.entities, entity) {
+            None
+        } else {
+            Some(format!(
+                "may not change {:?} from a {:?} export",
+                entity, scope.category
+            ))
+        }
+    })
+}
+
+/// Traps unless the running export may spawn and despawn entities.
+pub fn check_spawn(import: &'static str) -> Result<(), RuntimeError> {
+    check(import, |scope| {
+        if scope.category.may_spawn() {
+            None
+        } else {
+            Some(format!(
+                "may not be called from a {:?} export",
+                scope.category
+            ))
+        }
+    })
+}
+
+/// The entity whose export is running, the first one it was handed.
+pub fn scope_owner() -> Option<Entity> {
+    SCRIPT_SCOPE.with(|current| {
+        current
+            .borrow()
+            .as_ref()
+            .and_then(|scope| scope.entities.first().copied())
+    })
+}
+
+/// Lets the running export keep working on an entity it just spawned.
+pub fn add_to_scope(entity: Entity) {
+    SCRIPT_SCOPE.with(|current| {
+        if let Some(scope) = current.borrow_mut().as_mut() {
+            scope.entities.push(entity);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        battle::Faction,
+        script_testing::{ScriptTest, SOLDIER},
+    };
+
+    #[test]
+    fn battle_scripts_may_not_heal_other_troops() {
+        let mut test = ScriptTest::new();
+        let troop = test.scripted_soldier(
+            r#"(module
+                (import "env" "scan_enemies" (func $scan_enemies (param f64)))
+                (import "env" "get_enemy" (func $get_enemy (param f64 i32) (result f64)))
+                (import "env" "heal_troop" (func $heal_troop (param f64 i32)))
+                (func (export "on_death") (param f64) (result i32) i32.const 1)
+                (func (export "battle_action") (param $me f64) (result f32)
+                    (call $scan_enemies (local.get $me))
+                    (call $heal_troop (call $get_enemy (local.get $me) (i32.const 0)) (i32.const 5))
+                    f32.const 1))"#,
+        );
+        let enemy = test.troop(SOLDIER, Faction::enemy(), Vec2::new(20., 0.));
+        test.world().get_mut::<Troop>(enemy).unwrap().health = 1;
+
+        let err = test.try_battle_action(troop).unwrap_err();
+        assert!(err.contains("heal_troop may not change"), "{}", err);
+        assert_eq!(test.world().get::<Troop>(enemy).unwrap().health, 1);
+    }
+
+    #[test]
+    fn scopes_are_restored_when_a_call_panics() {
+        let outer = Entity::from_raw(1);
+        with_scope(ScriptCategory::Battle, &[outer], || {
+            let inner = std::panic::catch_unwind(|| {
+                with_scope(ScriptCategory::Delivery, &[Entity::from_raw(2)], || {
+                    panic!("trapped")
+                })
+            });
+            assert!(inner.is_err());
+            assert_eq!(scope_owner(), Some(outer));
+        });
+        assert_eq!(scope_owner(), None);
+    }
+}
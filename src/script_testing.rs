@@ -0,0 +1,446 @@
+//! Runs the compiled scripts in `assets/scripts` against a small headless world, with the same
+//! imports the game gives them, so a test can call an export and check what it did.
+
+use std::sync::{Arc, Mutex};
+
+use bevy::{ecs::system::System, prelude::*};
+use bevy_wasm_scripting::*;
+
+use crate::{
+    battle::{
+        troop_battle_action_system, troop_damaged_hook_system, Faction, Troop, TroopCooldown,
+        TroopDamaged, TroopTypes,
+    },
+    common_scripting::{compile_script, ScriptValues},
+    delivery::{DeliveryDropoff, DeliverySource},
+    harvest::{spawn_harvest_spot, Harvestable, HarvestableBundle, HarvestableTypes},
+    headless::HeadlessBattle,
+    loading::DeliveryScripts,
+    script_abi::{validate_script, ScriptRole},
+    script_errors::ScriptError,
+    script_scope::{with_scope, ScriptCategory},
+};
+
+pub const SOLDIER: i32 = 0;
+
+/// A headless battle that tests drive by hand. Spawn what a test needs, call an export, then look
+/// at `world()`.
+pub struct ScriptTest {
+    pub battle: HeadlessBattle,
+}
+
+impl ScriptTest {
+    pub fn new() -> Self {
+        Self {
+            battle: HeadlessBattle::new(App::new()).unwrap(),
+        }
+    }
+
+    pub fn world(&mut self) -> &mut World {
+        self.battle.world()
+    }
+
+    pub fn troop(&mut self, troop_id: i32, faction: Faction, position: Vec2) -> Entity {
+        let troop_type = self
+            .battle
+            .app
+            .world
+            .resource::<TroopTypes>()
+            .get(troop_id)
+            .unwrap();
+        self.battle.spawn_troop(troop_type, faction, position)
+    }
+
+    /// A troop of `troop_id` running `wat` instead of its own script.
+    pub fn scripted_troop(
+        &mut self,
+        troop_id: i32,
+        faction: Faction,
+        position: Vec2,
+        wat: &str,
+    ) -> Entity {
+        let mut troop_type = self
+            .battle
+            .app
+            .world
+            .resource::<TroopTypes>()
+            .get(troop_id)
+            .unwrap();
+        let wasm_bytes = compile_script("test.wat", wat.as_bytes().to_vec()).unwrap();
+        troop_type.exports = validate_script("test.wat", ScriptRole::Troop, &wasm_bytes).unwrap();
+        troop_type.script = Some(self.battle.add_script("test.wat".to_string(), wasm_bytes));
+        self.battle.app.update();
+        self.battle.spawn_troop(troop_type, faction, position)
+    }
+
+    /// A player soldier at the origin running `wat`, which is where most script tests start.
+    pub fn scripted_soldier(&mut self, wat: &str) -> Entity {
+        self.scripted_troop(SOLDIER, Faction::player(), Vec2::new(0., 0.), wat)
+    }
+
+    pub fn delivery_scripts(&self) -> &DeliveryScripts {
+        self.battle.app.world.resource::<DeliveryScripts>()
+    }
+
+    pub fn harvest_spot(&mut self, script: Handle<WasmScript>, position: Vec2) -> Entity {
+        self.battle.spawn(|commands| {
+            spawn_harvest_spot(
+                commands,
+                position,
+                Handle::default(),
+                Handle::default(),
+                script,
+                Visibility::VISIBLE,
+            )
+        })
+    }
+
+    pub fn staging_spot(&mut self, position: Vec2) -> Entity {
+        self.battle.staging_spot(position)
+    }
+
+    /// A harvestable being dragged around, `real` once it has grown.
+    pub fn harvestable(&mut self, harvestable_id: i32, real: bool) -> Entity {
+        let harvestable_type = self
+            .battle
+            .app
+            .world
+            .resource::<HarvestableTypes>()
+            .get(harvestable_id)
+            .unwrap();
+        self.battle
+            .app
+            .world
+            .spawn(HarvestableBundle {
+                sprite: Default::default(),
+                harvestable: Harvestable(harvestable_type, real),
+            })
+            .id()
+    }
+
+    pub fn set_script_value(&mut self, entity: Entity, script_value_id: i32, value: f32) {
+        self.battle
+            .app
+            .world
+            .get_mut::<ScriptValues>(entity)
+            .unwrap()
+            .0
+            .insert(script_value_id, value);
+    }
+
+    pub fn script_value(&self, entity: Entity, script_value_id: i32) -> Option<f32> {
+        self.battle
+            .app
+            .world
+            .get::<ScriptValues>(entity)
+            .and_then(|values| values.0.get(&script_value_id))
+            .cloned()
+    }
+
+    /// Runs `system` once as a script system, applying its commands afterwards like the game
+    /// does.
+    fn run_script_system<C: WasmScriptComponent, Params>(
+        &mut self,
+        system: impl IntoSystem<(), (), Params>,
+    ) {
+        let mut system = ScriptSystemWithCommands::<_, C>::wrap(IntoSystem::into_system(system));
+        system.initialize(&mut self.battle.app.world);
+        system.run((), &mut self.battle.app.world);
+        system.apply_buffers(&mut self.battle.app.world);
+    }
+
+    /// Runs `call` as a one-off script system and returns what it returned.
+    pub fn call<C: WasmScriptComponent, T: Send + 'static>(
+        &mut self,
+        mut call: impl FnMut(&mut WasmScriptComponentEnv<C, ()>) -> T + Send + Sync + 'static,
+    ) -> T {
+        let output = Arc::new(Mutex::new(None));
+        let sink = output.clone();
+        self.run_script_system::<C, _>(move |mut script_env: WasmScriptComponentEnv<C, ()>| {
+            *sink.lock().unwrap() = Some(call(&mut script_env));
+        });
+        let result = output.lock().unwrap().take();
+        result.unwrap()
+    }
+
+    /// The errors scripts reported since the last time, oldest first.
+    fn take_script_errors(&mut self) -> Vec<String> {
+        self.world()
+            .resource_mut::<Events<ScriptError>>()
+            .drain()
+            .map(|error| error.error)
+            .collect()
+    }
+
+    fn assert_troop_instantiated(&mut self, troop: Entity) {
+        let script = self.troop_script(troop);
+        self.call::<Troop, _>(move |script_env| assert_instantiated(script_env, &script));
+    }
+
+    fn troop_script(&self, troop: Entity) -> Handle<WasmScript> {
+        self.battle
+            .app
+            .world
+            .get::<Troop>(troop)
+            .unwrap()
+            .get_wasm_script_handle()
+            .clone()
+    }
+
+    fn dropoff_script(&self, dropoff: Entity) -> Handle<WasmScript> {
+        self.battle
+            .app
+            .world
+            .get::<DeliveryDropoff>(dropoff)
+            .unwrap()
+            .script
+            .clone()
+    }
+
+    pub fn battle_action(&mut self, troop: Entity) -> f32 {
+        self.try_battle_action(troop)
+            .unwrap_or_else(|err| panic!("battle_action failed: {}", err))
+    }
+
+    /// Runs the game's `troop_battle_action_system` with every other troop held back by a
+    /// cooldown. Returns the cooldown `troop` asked for, or the error its script reported.
+    pub fn try_battle_action(&mut self, troop: Entity) -> Result<f32, String> {
+        self.assert_troop_instantiated(troop);
+        let world = self.world();
+        let others: Vec<Entity> = world
+            .query_filtered::<Entity, (With<Troop>, Without<TroopCooldown>)>()
+            .iter(world)
+            .filter(|other| *other != troop)
+            .collect();
+        for other in &others {
+            world.entity_mut(*other).insert(TroopCooldown(f32::MAX));
+        }
+        world.entity_mut(troop).remove::<TroopCooldown>();
+
+        self.run_script_system::<Troop, _>(troop_battle_action_system);
+
+        let world = self.world();
+        for other in others {
+            if let Some(mut other) = world.get_entity_mut(other) {
+                other.remove::<TroopCooldown>();
+            }
+        }
+        let cooldown = world
+            .get::<TroopCooldown>(troop)
+            .map_or(0., |cooldown| cooldown.0);
+        match self.take_script_errors().pop() {
+            Some(err) => Err(err),
+            None => Ok(cooldown),
+        }
+    }
+
+    /// Runs the game's `on_damaged` hook system for a single hit.
+    pub fn on_damaged(&mut self, troop: Entity, attacker: Entity, amount: i32) {
+        self.assert_troop_instantiated(troop);
+        let mut events = self.world().resource_mut::<Events<TroopDamaged>>();
+        events.clear();
+        events.send(TroopDamaged {
+            target: troop,
+            attacker,
+            amount,
+        });
+        self.run_script_system::<Troop, _>(troop_damaged_hook_system);
+        if let Some(err) = self.take_script_errors().pop() {
+            panic!("on_damaged failed: {}", err);
+        }
+    }
+
+    pub fn can_receive(&mut self, dropoff: Entity, delivered: Entity) -> bool {
+        let script = self.dropoff_script(dropoff);
+        self.call::<DeliverySource, _>(move |script_env| {
+            assert_instantiated(script_env, &script);
+            with_scope(ScriptCategory::Delivery, &[dropoff, delivered], || {
+                script_env.call_if_instantiated_2::<EntityId, EntityId, i8>(
+                    &script,
+                    "can_receive",
+                    EntityId::from_entity(dropoff),
+                    EntityId::from_entity(delivered),
+                )
+            })
+            .unwrap_or_else(|err| panic!("can_receive failed: {}", err))
+                == 1
+        })
+    }
+
+    pub fn receive(&mut self, dropoff: Entity, delivered: Entity, from: Entity) {
+        let script = self.dropoff_script(dropoff);
+        self.call::<DeliverySource, _>(move |script_env| {
+            assert_instantiated(script_env, &script);
+            with_scope(
+                ScriptCategory::Delivery,
+                &[dropoff, delivered, from],
+                || {
+                    script_env.call_if_instantiated_3::<EntityId, EntityId, EntityId, ()>(
+                        &script,
+                        "receive",
+                        EntityId::from_entity(dropoff),
+                        EntityId::from_entity(delivered),
+                        EntityId::from_entity(from),
+                    )
+                },
+            )
+            .unwrap_or_else(|err| panic!("receive failed: {}", err))
+        })
+    }
+}
+
+/// `call_if_instantiated_*` returns the default for a script that isn't instantiated yet, which
+/// would let a test pass without running it. No script has this export, so asking for it only
+/// fails once the script is running.
+fn assert_instantiated<C: WasmScriptComponent>(
+    script_env: &mut WasmScriptComponentEnv<C, ()>,
+    script: &Handle<WasmScript>,
+) {
+    assert!(
+        script_env
+            .call_if_instantiated_1::<i32, ()>(script, "__not_an_export", 0)
+            .is_err(),
+        "script was called before it was instantiated"
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        attacks::Attack,
+        battle::StagingLocation,
+        common_scripting::{Quarantined, ATTACK_SPEED_MOD_ID},
+        harvest::HarvestSpot,
+    };
+
+    const WARRIOR: i32 = 2;
+    const ENRAGED_ID: i32 = 100;
+    const RED_BERRIES: i32 = 0;
+    const SOLDIER_HARVESTABLE: i32 = 4;
+
+    #[test]
+    fn looping_scripts_run_out_of_fuel_and_go_idle() {
+        let mut test = ScriptTest::new();
+        let troop = test.scripted_soldier(
+            r#"(module
+                (func (export "on_death") (param f64) (result i32) i32.const 1)
+                (func (export "battle_action") (param f64) (result f32)
+                    (loop $forever (br $forever))
+                    f32.const 1))"#,
+        );
+
+        assert!(test.try_battle_action(troop).is_err());
+        assert!(test.world().get::<Quarantined>(troop).is_some());
+        // The quarantined troop isn't called again, so it neither loops nor reports twice.
+        assert_eq!(test.try_battle_action(troop), Ok(0.));
+    }
+
+    #[test]
+    fn scripts_tune_the_attacks_they_define() {
+        let mut test = ScriptTest::new();
+        let troop = test.scripted_soldier(
+            r#"(module
+                (import "env" "define_attack" (func $define (param f64 i32 i32) (result i32)))
+                (import "env" "set_attack_cooldown"
+                    (func $cooldown (param f64 i32 f32) (result i32)))
+                (import "env" "scan_enemies" (func $scan_enemies (param f64)))
+                (import "env" "get_enemy" (func $get_enemy (param f64 i32) (result f64)))
+                (import "env" "attack_enemy" (func $attack (param f64 f64 i32) (result f32)))
+                (func (export "on_death") (param f64) (result i32) i32.const 1)
+                (func (export "battle_action") (param $me f64) (result f32)
+                    (drop (call $define (local.get $me) (i32.const 1) (i32.const 10)))
+                    (drop (call $cooldown (local.get $me) (i32.const 10) (f32.const 0.25)))
+                    (call $scan_enemies (local.get $me))
+                    (call $attack (local.get $me)
+                        (call $get_enemy (local.get $me) (i32.const 0)) (i32.const 10))))"#,
+        );
+        let enemy = test.troop(SOLDIER, Faction::enemy(), Vec2::new(20., 0.));
+
+        // How attacks are defined and who may change them is tested in `attacks`, this only
+        // checks a script gets there through its imports.
+        assert_eq!(test.battle_action(troop), 0.25);
+        let mut attacks = test.world().query::<&Attack>();
+        let attack = attacks.single(test.world());
+        assert_eq!((attack.attacker(), attack.target()), (troop, enemy));
+    }
+
+    #[test]
+    fn soldier_walks_towards_a_distant_enemy() {
+        let mut test = ScriptTest::new();
+        let soldier = test.troop(SOLDIER, Faction::player(), Vec2::new(0., 0.));
+        test.troop(SOLDIER, Faction::enemy(), Vec2::new(200., 0.));
+
+        // Soldiers act on what they saw last step, so the first action only scans.
+        test.battle_action(soldier);
+        assert_eq!(test.battle_action(soldier), 0.1);
+        let troop = test.world().get::<Troop>(soldier).unwrap();
+        let (target, speed) = troop.target.unwrap();
+        assert_eq!(target, Vec2::new(200., 0.));
+        assert_eq!(speed, troop.troop_type.move_speed);
+    }
+
+    #[test]
+    fn soldier_attacks_an_enemy_in_range() {
+        let mut test = ScriptTest::new();
+        let soldier = test.troop(SOLDIER, Faction::player(), Vec2::new(0., 0.));
+        let enemy = test.troop(SOLDIER, Faction::enemy(), Vec2::new(20., 0.));
+
+        test.battle_action(soldier);
+        assert!(test.battle_action(soldier) > 0.);
+        let mut attacks = test.world().query::<&Attack>();
+        let attack = attacks.single(test.world());
+        assert_eq!(attack.attacker(), soldier);
+        assert_eq!(attack.target(), enemy);
+    }
+
+    #[test]
+    fn warrior_enrages_below_half_health() {
+        let mut test = ScriptTest::new();
+        let warrior = test.troop(WARRIOR, Faction::player(), Vec2::new(0., 0.));
+        let enemy = test.troop(SOLDIER, Faction::enemy(), Vec2::new(20., 0.));
+
+        test.on_damaged(warrior, enemy, 1);
+        assert_eq!(test.script_value(warrior, ENRAGED_ID), None);
+
+        let mut troop = test.world().get_mut::<Troop>(warrior).unwrap();
+        troop.health = troop.troop_type.health / 2;
+        test.on_damaged(warrior, enemy, 1);
+        assert_eq!(test.script_value(warrior, ENRAGED_ID), Some(1.));
+        assert_eq!(test.script_value(warrior, ATTACK_SPEED_MOD_ID), Some(0.5));
+    }
+
+    #[test]
+    fn field_spot_plants_seeds() {
+        let mut test = ScriptTest::new();
+        let field_spot = test.delivery_scripts().field_spot.clone();
+        let spot = test.harvest_spot(field_spot, Vec2::new(48., 32.));
+        let seed = test.harvestable(RED_BERRIES, false);
+        let ripe = test.harvestable(RED_BERRIES, true);
+
+        assert!(!test.can_receive(spot, ripe));
+        assert!(test.can_receive(spot, seed));
+        test.receive(spot, seed, spot);
+        let harvest_spot = test.world().get::<HarvestSpot>(spot).unwrap();
+        assert_eq!(
+            harvest_spot.harvestable_type.as_ref().unwrap().id,
+            RED_BERRIES
+        );
+        assert!(test.world().get_entity(seed).is_none());
+    }
+
+    #[test]
+    fn staging_spot_stages_trained_troops() {
+        let mut test = ScriptTest::new();
+        let staging = test.staging_spot(Vec2::new(256., 0.));
+        let berries = test.harvestable(RED_BERRIES, true);
+        let soldier = test.harvestable(SOLDIER_HARVESTABLE, true);
+
+        assert!(!test.can_receive(staging, berries));
+        assert!(test.can_receive(staging, soldier));
+        test.receive(staging, soldier, staging);
+        let staged = &test.world().get::<StagingLocation>(staging).unwrap().staged;
+        assert_eq!(staged.len(), 1);
+        assert_eq!(staged[0].0, SOLDIER);
+    }
+}
@@ -0,0 +1,155 @@
+use bevy::{ecs::schedule::ShouldRun, prelude::*, transform::TransformSystem};
+
+use crate::{scenario::ObjectiveProgress, GameState};
+
+/// Battle, attack and harvest systems advance in steps of this many seconds, whatever the frame rate.
+pub const BATTLE_TIMESTEP: f32 = 1. / 60.;
+
+// Never try to catch up on more than this many steps in a single frame.
+const MAX_STEPS_PER_FRAME: u32 = 16;
+
+#[derive(StageLabel)]
+pub struct BattleStage;
+
+pub struct SimulationPlugin;
+
+impl Plugin for SimulationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BattleClock>()
+            .add_system_set(SystemSet::on_enter(GameState::Playing).with_system(reset_battle_clock))
+            .add_stage_before(
+                CoreStage::Update,
+                BattleStage,
+                SystemStage::parallel().with_run_criteria(battle_timestep),
+            )
+            .add_system_to_stage(CoreStage::PreUpdate, restore_simulated_translation)
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                interpolate_translation.before(TransformSystem::TransformPropagate),
+            );
+    }
+}
+
+#[derive(Resource)]
+pub struct BattleClock {
+    accumulator: f32,
+    steps_this_frame: u32,
+    pub steps: u64,
+    /// Multiplier on real time. Zero pauses the simulation.
+    pub speed: f32,
+}
+
+impl Default for BattleClock {
+    fn default() -> Self {
+        Self {
+            accumulator: 0.,
+            steps_this_frame: 0,
+            steps: 0,
+            speed: 1.,
+        }
+    }
+}
+
+impl BattleClock {
+    pub fn is_paused(&self) -> bool {
+        self.speed <= 0.
+    }
+
+    pub fn delta_seconds(&self) -> f32 {
+        BATTLE_TIMESTEP
+    }
+
+    /// Simulated seconds so far, which don't depend on the frame rate or game speed.
+    pub fn elapsed_seconds(&self) -> f64 {
+        self.steps as f64 * BATTLE_TIMESTEP as f64
+    }
+
+    /// How far rendering is between the last step and the next one, from 0 to 1.
+    pub fn overstep_fraction(&self) -> f32 {
+        (self.accumulator / BATTLE_TIMESTEP).clamp(0., 1.)
+    }
+}
+
+// Every game starts from step zero, without time banked in the menu or a previous game.
+fn reset_battle_clock(mut clock: ResMut<BattleClock>) {
+    *clock = BattleClock::default();
+}
+
+// Runs before every step, so it also records where each interpolated entity started that step.
+// The battle only runs while playing, and once the scenario is won or lost it stops where it is.
+fn battle_timestep(
+    mut clock: ResMut<BattleClock>,
+    mut looping: Local<bool>,
+    mut interpolated: Query<(&mut BattleInterpolation, &Transform)>,
+    time: Res<Time>,
+    state: Res<State<GameState>>,
+    progress: Option<Res<ObjectiveProgress>>,
+) -> ShouldRun {
+    if *state.current() != GameState::Playing
+        || progress.map_or(false, |progress| progress.outcome.is_some())
+    {
+        *looping = false;
+        return ShouldRun::No;
+    }
+    if !*looping {
+        clock.accumulator += time.delta_seconds() * clock.speed.max(0.);
+        clock.steps_this_frame = 0;
+    }
+    if clock.accumulator >= BATTLE_TIMESTEP && clock.steps_this_frame < MAX_STEPS_PER_FRAME {
+        clock.accumulator -= BATTLE_TIMESTEP;
+        clock.steps_this_frame += 1;
+        clock.steps += 1;
+        for (mut interpolation, transform) in interpolated.iter_mut() {
+            interpolation.previous = transform.translation;
+        }
+        *looping = true;
+        ShouldRun::YesAndCheckAgain
+    } else {
+        if clock.accumulator >= BATTLE_TIMESTEP {
+            // Too far behind, so drop the backlog rather than spiral.
+            clock.accumulator = clock.accumulator % BATTLE_TIMESTEP;
+        }
+        *looping = false;
+        ShouldRun::No
+    }
+}
+
+/// Entities moved by the battle simulation. Their `Transform` holds the interpolated position
+/// between frames, and the simulated position during `BattleStage`.
+#[derive(Component)]
+pub struct BattleInterpolation {
+    previous: Vec3,
+    current: Vec3,
+}
+
+impl BattleInterpolation {
+    pub fn new(translation: Vec3) -> Self {
+        Self {
+            previous: translation,
+            current: translation,
+        }
+    }
+
+    /// Call after teleporting an entity, so it doesn't visibly slide to its new position.
+    pub fn snap(&mut self, translation: Vec3) {
+        self.previous = translation;
+        self.current = translation;
+    }
+}
+
+fn restore_simulated_translation(mut query: Query<(&BattleInterpolation, &mut Transform)>) {
+    for (interpolation, mut transform) in query.iter_mut() {
+        transform.translation = interpolation.current;
+    }
+}
+
+fn interpolate_translation(
+    clock: Res<BattleClock>,
+    mut query: Query<(&mut BattleInterpolation, &mut Transform)>,
+) {
+    let alpha = clock.overstep_fraction();
+    for (mut interpolation, mut transform) in query.iter_mut() {
+        interpolation.current = transform.translation;
+        transform.translation = interpolation.previous.lerp(interpolation.current, alpha);
+    }
+}
@@ -0,0 +1,129 @@
+use bevy::prelude::*;
+
+use crate::{loading::FontAssets, simulation::BattleClock, GameState};
+
+pub const GAME_SPEEDS: [(f32, &str, KeyCode); 5] = [
+    (0., "||", KeyCode::Key0),
+    (0.5, "0.5x", KeyCode::Key1),
+    (1., "1x", KeyCode::Key2),
+    (2., "2x", KeyCode::Key3),
+    (4., "4x", KeyCode::Key4),
+];
+
+pub struct SpeedPlugin;
+
+impl Plugin for SpeedPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_enter(GameState::Playing).with_system(setup_speed_controls),
+        )
+        .add_system_set(SystemSet::on_update(GameState::Playing).with_system(speed_keyboard_system))
+        .add_system_set(SystemSet::on_update(GameState::Playing).with_system(speed_button_system))
+        .add_system_set(
+            SystemSet::on_update(GameState::Playing).with_system(speed_button_color_system),
+        );
+    }
+}
+
+const BUTTON_NORMAL: Color = Color::rgb(0.15, 0.15, 0.15);
+const BUTTON_HOVERED: Color = Color::rgb(0.25, 0.25, 0.25);
+const BUTTON_SELECTED: Color = Color::rgb(0.2, 0.4, 0.2);
+
+#[derive(Component)]
+pub struct SpeedButton(pub f32);
+
+fn setup_speed_controls(
+    mut commands: Commands,
+    fonts: Res<FontAssets>,
+    mut clock: ResMut<BattleClock>,
+) {
+    clock.speed = 1.;
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    bottom: Val::Px(8.),
+                    right: Val::Px(8.),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .with_children(|parent| {
+            for (speed, label, _key) in GAME_SPEEDS.iter() {
+                parent
+                    .spawn((
+                        ButtonBundle {
+                            style: Style {
+                                size: Size::new(Val::Px(40.), Val::Px(24.)),
+                                margin: UiRect::all(Val::Px(2.)),
+                                justify_content: JustifyContent::Center,
+                                align_items: AlignItems::Center,
+                                ..Default::default()
+                            },
+                            background_color: BUTTON_NORMAL.into(),
+                            ..Default::default()
+                        },
+                        SpeedButton(*speed),
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn(TextBundle::from_section(
+                            *label,
+                            TextStyle {
+                                font: fonts.fira_sans.clone(),
+                                font_size: 14.,
+                                color: Color::rgb(0.9, 0.9, 0.9),
+                            },
+                        ));
+                    });
+            }
+        });
+}
+
+fn speed_keyboard_system(
+    mut clock: ResMut<BattleClock>,
+    mut last_speed: Local<Option<f32>>,
+    input: Res<Input<KeyCode>>,
+) {
+    if input.just_pressed(KeyCode::Space) {
+        if clock.is_paused() {
+            clock.speed = last_speed.take().unwrap_or(1.);
+        } else {
+            *last_speed = Some(clock.speed);
+            clock.speed = 0.;
+        }
+    }
+    for (speed, _label, key) in GAME_SPEEDS.iter() {
+        if input.just_pressed(*key) {
+            clock.speed = *speed;
+        }
+    }
+}
+
+fn speed_button_system(
+    mut clock: ResMut<BattleClock>,
+    interaction_query: Query<(&Interaction, &SpeedButton), Changed<Interaction>>,
+) {
+    for (interaction, button) in interaction_query.iter() {
+        if *interaction == Interaction::Clicked {
+            clock.speed = button.0;
+        }
+    }
+}
+
+fn speed_button_color_system(
+    clock: Res<BattleClock>,
+    mut buttons: Query<(&Interaction, &SpeedButton, &mut BackgroundColor)>,
+) {
+    for (interaction, button, mut color) in buttons.iter_mut() {
+        *color = if button.0 == clock.speed {
+            BUTTON_SELECTED.into()
+        } else if *interaction == Interaction::Hovered {
+            BUTTON_HOVERED.into()
+        } else {
+            BUTTON_NORMAL.into()
+        };
+    }
+}
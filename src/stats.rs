@@ -0,0 +1,165 @@
+use bevy::{
+    asset::{AssetLoader, LoadedAsset},
+    prelude::*,
+    reflect::TypeUuid,
+    utils::HashMap,
+};
+use serde::Deserialize;
+
+use crate::loading::StatAssets;
+
+pub struct StatPlugin;
+
+impl Plugin for StatPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<StatType>()
+            .init_asset_loader::<StatAssetLoader>();
+    }
+}
+
+/// How a stat's value reads in tooltips.
+#[derive(Clone, Deserialize)]
+pub enum StatDisplay {
+    /// How many steps of `step` the value is from the default, like stacked buffs.
+    Steps { step: f32 },
+    /// The difference from the default, as a signed percentage.
+    Percent,
+}
+
+/// A script value with a name, bounds and a way to show it. The game reads some of them itself
+/// (see the ids in `common_scripting`), mods are free to add more.
+#[derive(Clone, Deserialize, TypeUuid)]
+#[uuid = "b903c0ac-290c-48b6-bdc5-149ea21846d6"]
+pub struct StatType {
+    pub key: String,
+    pub id: i32,
+    pub label: String,
+    pub default: f32,
+    pub min: f32,
+    pub max: f32,
+    pub display: StatDisplay,
+    #[serde(default)]
+    pub icon: Option<String>,
+}
+
+impl StatType {
+    pub fn describe(&self, value: f32) -> String {
+        let shown = match self.display {
+            StatDisplay::Steps { step } => format!("{}", ((value - self.default) / step) as i32),
+            StatDisplay::Percent => format!("{:+}%", ((value - self.default) * 100.) as i32),
+        };
+        match &self.icon {
+            Some(icon) => format!("{} {} {}", icon, self.label, shown),
+            None => format!("{} {}", self.label, shown),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct StatAssetLoader;
+
+impl AssetLoader for StatAssetLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut bevy::asset::LoadContext,
+    ) -> bevy::utils::BoxedFuture<'a, anyhow::Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let custom_asset = ron::de::from_bytes::<StatType>(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(custom_asset));
+            Ok(())
+        })
+    }
+    fn extensions(&self) -> &[&str] {
+        &["stat"]
+    }
+}
+
+#[derive(Resource)]
+pub struct StatTypes(pub HashMap<i32, StatType>);
+
+impl FromWorld for StatTypes {
+    fn from_world(world: &mut World) -> Self {
+        let stats = world.get_resource::<StatAssets>().unwrap();
+        let assets = world.get_resource::<Assets<StatType>>().unwrap();
+        let mut map = HashMap::new();
+        stats.stats.iter().for_each(|stat| {
+            if let Some(stat) = assets.get(stat) {
+                map.insert(stat.id, stat.clone());
+            }
+        });
+        Self(map)
+    }
+}
+
+impl StatTypes {
+    pub fn get(&self, id: i32) -> Option<&StatType> {
+        self.0.get(&id)
+    }
+
+    pub fn by_key(&self, key: &str) -> Option<&StatType> {
+        self.0.values().find(|stat| stat.key == key)
+    }
+
+    /// Every stat, in id order.
+    pub fn sorted(&self) -> Vec<&StatType> {
+        let mut stats: Vec<&StatType> = self.0.values().collect();
+        stats.sort_by_key(|stat| stat.id);
+        stats
+    }
+
+    /// Keeps `value` within the bounds of stat `id`. Values without a stat are left alone.
+    pub fn clamp(&self, id: i32, value: f32) -> f32 {
+        match self.get(id) {
+            Some(stat) => value.clamp(stat.min, stat.max),
+            None => value,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stat(key: &str, id: i32, label: &str, max: f32, display: StatDisplay) -> StatType {
+        StatType {
+            key: key.to_string(),
+            id,
+            label: label.to_string(),
+            default: 0.,
+            min: -0.5,
+            max,
+            display,
+            icon: None,
+        }
+    }
+
+    #[test]
+    fn stats_read_like_the_tooltip() {
+        let attack_range = stat("attack_range", 2, "RNG", 2., StatDisplay::Percent);
+        let dodge_chance = stat(
+            "dodge_chance",
+            3,
+            "DODGE",
+            0.75,
+            StatDisplay::Steps { step: 0.05 },
+        );
+        let stat_types = StatTypes(HashMap::from_iter([(2, attack_range), (3, dodge_chance)]));
+        let dodge_chance = stat_types.by_key("dodge_chance").unwrap();
+        assert_eq!(dodge_chance.id, 3);
+        assert_eq!(dodge_chance.describe(0.1), "DODGE 2");
+        let attack_range = stat_types.get(2).unwrap();
+        assert_eq!(attack_range.describe(0.25), "RNG +25%");
+    }
+
+    #[test]
+    fn values_stay_within_their_stat() {
+        let dodge_chance = stat("dodge_chance", 3, "DODGE", 0.75, StatDisplay::Percent);
+        let stat_types = StatTypes(HashMap::from_iter([(3, dodge_chance)]));
+        assert_eq!(stat_types.clamp(3, 5.), 0.75);
+        assert_eq!(stat_types.clamp(3, -1.), -0.5);
+        assert_eq!(stat_types.clamp(3, 0.2), 0.2);
+        // Script values without a stat, like a warrior's rage, are the script's business.
+        assert_eq!(stat_types.clamp(7, 5.), 5.);
+    }
+}
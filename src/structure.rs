@@ -0,0 +1,92 @@
+use bevy::prelude::*;
+
+use crate::{
+    delivery::{DeliveryDropoff, DeliverySource},
+    harvest::HarvestSpot,
+    simulation::BattleStage,
+    GameState,
+};
+
+pub struct StructurePlugin;
+
+impl Plugin for StructurePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set_to_stage(
+            BattleStage,
+            SystemSet::on_update(GameState::Playing).with_system(structure_ruin_system),
+        );
+    }
+}
+
+/// Farm buildings that enemies can attack.
+#[derive(Component)]
+pub struct Structure {
+    pub health: i32,
+    pub max_health: i32,
+    ruined: bool,
+}
+
+impl Structure {
+    pub fn new(health: i32) -> Self {
+        Self {
+            health,
+            max_health: health,
+            ruined: false,
+        }
+    }
+
+    pub fn is_destroyed(&self) -> bool {
+        self.health <= 0
+    }
+
+    /// Plots lose this fraction of their harvest time per point of damage.
+    pub fn crop_loss(&self, amount: i32) -> f32 {
+        amount as f32 / self.max_health.max(1) as f32
+    }
+}
+
+pub fn damage_structure(
+    structure: &mut Structure,
+    harvest_spot: Option<&mut HarvestSpot>,
+    amount: i32,
+) {
+    structure.health -= amount;
+    if let Some(harvest_spot) = harvest_spot {
+        if harvest_spot.harvestable_type.is_some() {
+            harvest_spot.progress = (harvest_spot.progress
+                - structure.crop_loss(amount) * harvest_spot.harvest_time)
+                .max(0.);
+        }
+    }
+}
+
+// Destroyed structures stop taking deliveries, and destroyed plots lose their crop.
+pub fn structure_ruin_system(
+    mut commands: Commands,
+    mut structures: Query<(
+        Entity,
+        &mut Structure,
+        &mut TextureAtlasSprite,
+        Option<&mut HarvestSpot>,
+    )>,
+) {
+    for (entity, mut structure, mut sprite, harvest_spot) in structures.iter_mut() {
+        if structure.ruined || !structure.is_destroyed() {
+            continue;
+        }
+        structure.ruined = true;
+        sprite.color = Color::DARK_GRAY;
+        if let Some(mut harvest_spot) = harvest_spot {
+            if let Some(harvestable) = harvest_spot.harvestable_entity {
+                if let Some(harvestable) = commands.get_entity(harvestable) {
+                    harvestable.despawn_recursive();
+                }
+            }
+            harvest_spot.set_harvestable(None);
+        }
+        if let Some(mut entity) = commands.get_entity(entity) {
+            entity.remove::<DeliverySource>();
+            entity.remove::<DeliveryDropoff>();
+        }
+    }
+}
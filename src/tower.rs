@@ -0,0 +1,157 @@
+use bevy::{prelude::*, utils::HashMap};
+
+use crate::{
+    battle::{spawn_troop, Faction, TroopTypes},
+    delivery::*,
+    harvest::spawn_harvest_spot,
+    helper::HelperTextBundle,
+    loading::*,
+    simulation::BattleStage,
+    structure::Structure,
+    GameState,
+};
+
+pub struct TowerPlugin;
+
+impl Plugin for TowerPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_enter(GameState::Playing).with_system(spawn_workshop))
+            .add_system_set(
+                SystemSet::on_enter(GameState::Playing).with_system(spawn_tower_foundations),
+            )
+            .add_system_set_to_stage(
+                BattleStage,
+                SystemSet::on_update(GameState::Playing).with_system(tower_building_system),
+            );
+    }
+}
+
+#[derive(Bundle)]
+pub struct WorkshopBundle {
+    sprite: SpriteSheetBundle,
+    delivery_dropoff: DeliveryDropoff,
+    delivery_anchor: DeliveryAnchor,
+    structure: Structure,
+    faction: Faction,
+}
+
+#[derive(Bundle)]
+pub struct TowerFoundationBundle {
+    sprite: SpriteSheetBundle,
+    tower_foundation: TowerFoundation,
+    delivery_dropoff: DeliveryDropoff,
+    delivery_anchor: DeliveryAnchor,
+}
+
+/// A spot that holds at most one tower. Once the tower falls, another can be built.
+#[derive(Component, Default)]
+pub struct TowerFoundation {
+    pub tower: Option<Entity>,
+    pub pending: Option<i32>,
+}
+
+impl TowerFoundation {
+    pub fn build(&mut self, tower_id: i32) {
+        self.pending = Some(tower_id);
+    }
+}
+
+fn spawn_workshop(
+    mut commands: Commands,
+    fonts: Res<FontAssets>,
+    textures: Res<TextureAssets>,
+    scripts: Res<DeliveryScripts>,
+) {
+    let helper = commands
+        .spawn(HelperTextBundle::new(
+            "Workshop - Send recruits to draft tower blueprints",
+            fonts.fira_sans.clone(),
+        ))
+        .id();
+    let spot = spawn_harvest_spot(
+        &mut commands,
+        Vec2::new(0., -36.),
+        fonts.fira_sans.clone(),
+        textures.harvest_base.clone(),
+        scripts.child_spot.clone(),
+        Visibility::INVISIBLE,
+    );
+    commands
+        .spawn(WorkshopBundle {
+            sprite: SpriteSheetBundle {
+                texture_atlas: textures.locations.clone(),
+                sprite: TextureAtlasSprite {
+                    index: 7,
+                    ..Default::default()
+                },
+                transform: Transform::from_translation(Vec3::new(128., -80., 1.)),
+                ..Default::default()
+            },
+            delivery_anchor: DeliveryAnchor::new(0., -16., 32., 32 * 32),
+            delivery_dropoff: DeliveryDropoff::new(scripts.workshop.clone()),
+            structure: Structure::new(20),
+            faction: Faction::player(),
+        })
+        .add_child(spot)
+        .add_child(helper);
+}
+
+fn spawn_tower_foundations(
+    mut commands: Commands,
+    fonts: Res<FontAssets>,
+    textures: Res<TextureAssets>,
+    scripts: Res<DeliveryScripts>,
+) {
+    for (x, y) in [(192., 128.), (-192., 128.), (192., -128.), (-192., -128.)].iter() {
+        let helper = commands
+            .spawn(HelperTextBundle::new(
+                "Tower Foundation - Drag blueprints here to build a tower",
+                fonts.fira_sans.clone(),
+            ))
+            .id();
+        commands
+            .spawn(TowerFoundationBundle {
+                sprite: SpriteSheetBundle {
+                    texture_atlas: textures.locations.clone(),
+                    sprite: TextureAtlasSprite {
+                        index: 8,
+                        ..Default::default()
+                    },
+                    transform: Transform::from_translation(Vec3::new(*x, *y, 0.5)),
+                    ..Default::default()
+                },
+                tower_foundation: Default::default(),
+                delivery_anchor: DeliveryAnchor::new(0., -16., 32., 32 * 32),
+                delivery_dropoff: DeliveryDropoff::new(scripts.tower_foundation.clone()),
+            })
+            .add_child(helper);
+    }
+}
+
+// Towers are ordinary troops that never move, so they fight through their troop script.
+fn tower_building_system(
+    mut commands: Commands,
+    mut foundations: Query<(&GlobalTransform, &mut TowerFoundation)>,
+    delivery_scripts: Res<DeliveryScripts>,
+    fonts: Res<FontAssets>,
+    textures: Res<TextureAssets>,
+    troop_types: Res<TroopTypes>,
+) {
+    for (transform, mut foundation) in foundations.iter_mut() {
+        if let Some(tower_id) = foundation.pending.take() {
+            if let Some(troop_type) = troop_types.get(tower_id) {
+                let tower = spawn_troop(
+                    &mut commands,
+                    Vec2::new(transform.translation().x, transform.translation().y),
+                    fonts.fira_sans.clone(),
+                    textures.troops.clone(),
+                    delivery_scripts.deliver_troop_buffs.clone(),
+                    troop_type,
+                    Faction::player(),
+                    HashMap::default(),
+                );
+                foundation.tower = Some(tower);
+            }
+        }
+    }
+}
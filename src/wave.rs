@@ -7,10 +7,14 @@ use bevy::{
 use serde::Deserialize;
 
 use crate::{
-    battle::{Faction, StagingLocation, Troop, TroopCooldown},
+    battle::{Faction, StagingLocation, Troop},
+    common_scripting::Quarantined,
+    director::Director,
     helper::HelperTextBundle,
     loading::{FontAssets, TextureAssets, WaveAssets},
-    GameState, SafeInsert,
+    scenario::{ObjectiveProgress, ObjectiveStatus, ScenarioOutcome},
+    simulation::{BattleClock, BattleStage},
+    GameState,
 };
 
 pub struct WavePlugin;
@@ -20,14 +24,15 @@ impl Plugin for WavePlugin {
         app.add_asset::<Wave>()
             .init_resource::<CurrentWave>()
             .init_resource::<InvasionSpots>()
+            .init_resource::<Events<WaveStarted>>()
             .init_asset_loader::<WaveAssetLoader>()
             .add_system_set(SystemSet::on_enter(GameState::Playing).with_system(setup_wave_ui))
             .add_system_set(
                 SystemSet::on_update(GameState::Playing).with_system(wave_describe_system),
             )
             .add_system_set(SystemSet::on_update(GameState::Playing).with_system(restart_game))
-            .add_system_set(SystemSet::on_update(GameState::Playing).with_system(game_over_system))
-            .add_system_set(
+            .add_system_set_to_stage(
+                BattleStage,
                 SystemSet::on_update(GameState::Playing).with_system(wave_spawning_system),
             )
             .add_system_set(
@@ -82,12 +87,20 @@ impl FromWorld for Waves {
     fn from_world(world: &mut World) -> Self {
         let waves = world.get_resource::<WaveAssets>().unwrap();
         let assets = world.get_resource::<Assets<Wave>>().unwrap();
-        let mut map = HashMap::new();
-        waves.waves.iter().for_each(|wave| {
-            if let Some(wave) = assets.get(wave) {
-                map.insert(wave.id, wave.clone());
-            }
-        });
+        Self::new(
+            waves
+                .waves
+                .iter()
+                .filter_map(|wave| assets.get(wave))
+                .cloned(),
+        )
+    }
+}
+
+impl Waves {
+    /// The authored waves, plus the idle wave -1 that runs between games.
+    pub fn new(waves: impl IntoIterator<Item = Wave>) -> Self {
+        let mut map: HashMap<i32, Wave> = waves.into_iter().map(|wave| (wave.id, wave)).collect();
         map.insert(
             -1,
             Wave {
@@ -100,9 +113,7 @@ impl FromWorld for Waves {
         );
         Self(map)
     }
-}
 
-impl Waves {
     pub fn get(&self, id: i32) -> Wave {
         self.0
             .get(&id)
@@ -110,6 +121,21 @@ impl Waves {
             .cloned()
             .unwrap()
     }
+
+    /// How many waves there are to play, leaving out the idle one.
+    pub fn authored(&self) -> i32 {
+        self.0.keys().filter(|id| **id >= 0).count() as i32
+    }
+
+    /// Whether any authored wave sends in troops of this type.
+    pub fn spawns(&self, troop_id: i32) -> bool {
+        self.0.values().any(|wave| {
+            [&wave.north, &wave.east, &wave.south, &wave.west]
+                .iter()
+                .flat_map(|side| side.iter())
+                .any(|(troop, _buffs)| *troop == troop_id)
+        })
+    }
 }
 
 pub const SPAWN_TIME: f32 = 5.;
@@ -166,6 +192,21 @@ impl CurrentWave {
         };
         self.time_in_wave = -60.;
     }
+    pub fn victory(&mut self) {
+        self.go_to_next_wave(Wave {
+            id: -3,
+            north: vec![],
+            east: vec![],
+            south: vec![],
+            west: vec![],
+        });
+    }
+    pub fn set_wave(&mut self, wave: Wave, time_in_wave: f32) {
+        self.wave = wave;
+        self.time_in_wave = time_in_wave;
+        self.score = 0.;
+        self.spawned = 0;
+    }
     pub fn go_to_next_wave(&mut self, wave: Wave) {
         self.score = self.score + wave.score(self.time_in_wave);
         self.wave = wave;
@@ -267,6 +308,7 @@ fn restart_game(
 
 fn wave_describe_system(
     current_wave: ResMut<CurrentWave>,
+    progress: Res<ObjectiveProgress>,
     fonts: Res<FontAssets>,
     mut wave_text: Query<(&mut Text), With<WaveText>>,
 ) {
@@ -276,34 +318,64 @@ fn wave_describe_system(
         font_size: 24.,
     };
     if current_wave.wave.id >= 0 {
-        *wave_text.single_mut() = Text::from_sections([
+        let mut sections = vec![
             TextSection::new(format!("Wave {}\n", current_wave.wave.id + 1), main_style),
             TextSection::new(
-                format!("Time - {}", current_wave.time_in_wave as i32),
+                format!("Time - {}\n", current_wave.time_in_wave as i32),
                 TextStyle {
                     color: Color::WHITE,
                     font: fonts.fira_sans.clone(),
                     font_size: 18.,
                 },
             ),
-        ]);
+        ];
+        for (description, status) in progress.descriptions.iter() {
+            sections.push(TextSection::new(
+                format!("{}\n", description),
+                TextStyle {
+                    color: match status {
+                        ObjectiveStatus::InProgress => Color::WHITE,
+                        ObjectiveStatus::Complete => Color::GREEN,
+                        ObjectiveStatus::Failed => Color::RED,
+                    },
+                    font: fonts.fira_sans.clone(),
+                    font_size: 14.,
+                },
+            ));
+        }
+        *wave_text.single_mut() = Text::from_sections(sections);
     } else if current_wave.wave.id == -1 {
         *wave_text.single_mut() = Text::from_sections([
             TextSection::new("Welcome!\n", main_style),
             TextSection::new(
-                "The first wave will spawn soon!\nRecruit, train, and deploy your troops!\nFeed them to give them buffs!",
+                "The first wave will spawn soon!\nRecruit, train, and deploy your troops!\nFeed them to give them buffs!\n",
                 TextStyle {
                     color: Color::WHITE,
                     font: fonts.fira_sans.clone(),
                     font_size: 18.,
                 },
             ),
+            TextSection::new(
+                progress
+                    .scenario
+                    .as_ref()
+                    .map(|scenario| scenario.description.clone())
+                    .unwrap_or_default(),
+                TextStyle {
+                    color: Color::YELLOW,
+                    font: fonts.fira_sans.clone(),
+                    font_size: 18.,
+                },
+            ),
         ]);
     } else if current_wave.wave.id == -2 {
         *wave_text.single_mut() = Text::from_sections([
             TextSection::new("Game Over!\n", main_style),
             TextSection::new(
-                "Your king has died...\n",
+                match &progress.outcome {
+                    Some(ScenarioOutcome::Lost(failed)) => format!("Failed: {}\n", failed),
+                    _ => "Your king has died...\n".to_string(),
+                },
                 TextStyle {
                     color: Color::RED,
                     font: fonts.fira_sans.clone(),
@@ -331,7 +403,10 @@ fn wave_describe_system(
         *wave_text.single_mut() = Text::from_sections([
             TextSection::new("Game Over!\n", main_style),
             TextSection::new(
-                "You have fended off the invaders!\n",
+                match &progress.outcome {
+                    Some(ScenarioOutcome::Won(message)) => format!("{}\n", message),
+                    _ => "You have fended off the invaders!\n".to_string(),
+                },
                 TextStyle {
                     color: Color::GREEN,
                     font: fonts.fira_sans.clone(),
@@ -358,47 +433,26 @@ fn wave_describe_system(
     }
 }
 
-fn game_over_system(
-    mut current_wave: ResMut<CurrentWave>,
-    mut commands: Commands,
-    troops: Query<(Entity, &Troop, &Faction)>,
-) {
-    if current_wave.wave.id < 0 {
-        // Pre-initialized state?
-        return;
-    }
-    for (entity, troop, faction) in troops.iter() {
-        if troop.troop_type.id == 87 {
-            return;
-        }
-    }
-    for (entity, _troop, faction) in troops.iter() {
-        if faction.faction_id == Faction::player().faction_id {
-            commands.add(SafeInsert::new(entity, TroopCooldown(999.)));
-        }
-    }
-    current_wave.game_over();
-}
-
+// Clearing the last wave doesn't win by itself, since the scenario's objectives may ask for more.
 fn wave_ending_system(
     mut current_wave: ResMut<CurrentWave>,
+    mut progress: ResMut<ObjectiveProgress>,
     enemies: Query<(&Troop, &Faction)>,
     waves: Res<Waves>,
+    directors: Query<(), (With<Director>, Without<Quarantined>)>,
 ) {
-    if current_wave.time_in_wave > SPAWN_TIME + 2. {
+    if !directors.is_empty() {
+        // Working directors end their own waves.
+        return;
+    }
+    if current_wave.wave.id >= 0 && current_wave.time_in_wave > SPAWN_TIME + 2. {
         if !enemies
             .iter()
             .any(|(_troop, faction)| faction.faction_id == Faction::enemy().faction_id)
         {
             let next_wave = waves.get(current_wave.wave.id + 1);
             if next_wave.id == -1 {
-                current_wave.go_to_next_wave(Wave {
-                    id: -3,
-                    north: vec![],
-                    east: vec![],
-                    south: vec![],
-                    west: vec![],
-                })
+                progress.all_waves_cleared = true;
             } else {
                 current_wave.go_to_next_wave(next_wave);
             }
@@ -406,17 +460,34 @@ fn wave_ending_system(
     }
 }
 
-fn wave_spawning_system(
+/// Sent once a wave's invaders start spawning.
+pub struct WaveStarted {
+    pub wave_id: i32,
+}
+
+pub fn wave_spawning_system(
     mut commands: Commands,
+    mut wave_events: EventWriter<WaveStarted>,
     fonts: Res<FontAssets>,
     textures: Res<TextureAssets>,
     mut current_wave: ResMut<CurrentWave>,
     mut invasions: ResMut<InvasionSpots>,
     mut staging: Query<&mut StagingLocation>,
-    time: Res<Time>,
+    clock: Res<BattleClock>,
+    directors: Query<(), (With<Director>, Without<Quarantined>)>,
 ) {
-    let delta_seconds = time.delta_seconds();
+    let delta_seconds = clock.delta_seconds();
+    let was_waiting = current_wave.time_in_wave <= 0.;
     current_wave.time_in_wave += delta_seconds;
+    if current_wave.time_in_wave > 0. && was_waiting && current_wave.wave.id >= 0 {
+        wave_events.send(WaveStarted {
+            wave_id: current_wave.wave.id,
+        });
+    }
+    if !directors.is_empty() {
+        // Working directors spawn their own invaders, the clock and wave events still run.
+        return;
+    }
     if current_wave.time_in_wave > 0. {
         let wanted_spawn =
             (current_wave.max_spawned() as f32 * current_wave.time_in_wave / SPAWN_TIME) as usize;